@@ -27,12 +27,14 @@ const windowsBuilds = [
         name: '标准32位Windows版本',
         target: 'i686-pc-windows-msvc',
         output: 'CMTools.x86.exe',
+        cliOutput: 'CMTools-cli.x86.exe',
         env: {}
     },
     {
         name: '标准64位Windows版本',
         target: 'x86_64-pc-windows-msvc',
         output: 'CMTools.x64.exe',
+        cliOutput: 'CMTools-cli.x64.exe',
         env: {}
     }
 ];
@@ -184,8 +186,25 @@ function cleanPreviousBuilds() {
     console.log('');
 }
 
+// 构建控制台子系统的命令行程序 cmtools-cli.exe 并复制到 cliOutput
+// Windows 7 兼容版通过 RUSTFLAGS 将所有程序链接为图形子系统，不提供命令行程序
+function buildCliExe(target, useTargetArg, cliOutput, buildEnv) {
+    console.log('   📦 构建命令行程序 cmtools-cli...');
+    const cargoCmd = useTargetArg
+        ? `cargo build --release --bin cmtools-cli --target ${target}`
+        : 'cargo build --release --bin cmtools-cli';
+    execSync(cargoCmd, { stdio: 'inherit', cwd: 'src-tauri', env: buildEnv });
+    const cliExePath = useTargetArg
+        ? `src-tauri/target/${target}/release/cmtools-cli.exe`
+        : 'src-tauri/target/release/cmtools-cli.exe';
+    if (!fs.existsSync(cliExePath)) {
+        throw new Error(`构建输出文件不存在: ${cliExePath}`);
+    }
+    fs.copyFileSync(cliExePath, cliOutput);
+}
+
 async function buildVersion(buildConfig) {
-    const { name, target, output, env } = buildConfig;
+    const { name, target, output, cliOutput, env } = buildConfig;
     
     console.log(`🔨 构建 ${name}...`);
     console.log(`   目标: ${target}`);
@@ -288,6 +307,9 @@ async function buildVersion(buildConfig) {
 
         // 复制并重命名文件
         fs.copyFileSync(sourcePath, output);
+        if (cliOutput) {
+            buildCliExe(target, useTargetArgForBuild, cliOutput, buildEnv);
+        }
         
         // 获取文件信息
         const stats = fs.statSync(output);
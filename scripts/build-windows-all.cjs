@@ -18,12 +18,14 @@ const builds = [
         name: 'Windows 64位版本',
         target: 'x86_64-pc-windows-msvc',
         output: 'CMTools.x64.exe',
+        cliOutput: 'CMTools-cli.x64.exe',
         description: '适用于64位Windows 10+系统'
     },
     {
         name: 'Windows 32位版本',
         target: 'i686-pc-windows-msvc',
         output: 'CMTools.x86.exe',
+        cliOutput: 'CMTools-cli.x86.exe',
         description: '适用于32位Windows 10+系统'
     },
     {
@@ -144,8 +146,25 @@ function cleanPreviousBuilds() {
     console.log('');
 }
 
+// 构建控制台子系统的命令行程序 cmtools-cli.exe 并复制到 cliOutput
+// Windows 7 兼容版通过 RUSTFLAGS 将所有程序链接为图形子系统，不提供命令行程序
+function buildCliExe(target, useTargetArg, cliOutput, buildEnv) {
+    console.log('   📦 构建命令行程序 cmtools-cli...');
+    const cargoCmd = useTargetArg
+        ? `cargo build --release --bin cmtools-cli --target ${target}`
+        : 'cargo build --release --bin cmtools-cli';
+    execSync(cargoCmd, { stdio: 'inherit', cwd: 'src-tauri', env: buildEnv });
+    const cliExePath = useTargetArg
+        ? `src-tauri/target/${target}/release/cmtools-cli.exe`
+        : 'src-tauri/target/release/cmtools-cli.exe';
+    if (!fs.existsSync(cliExePath)) {
+        throw new Error(`构建输出文件不存在: ${cliExePath}`);
+    }
+    fs.copyFileSync(cliExePath, cliOutput);
+}
+
 async function buildVersion(buildConfig) {
-    const { name, target, output, description, env = {}, win7 = false } = buildConfig;
+    const { name, target, output, cliOutput, description, env = {}, win7 = false } = buildConfig;
     
     console.log(`🔨 构建 ${name}...`);
     console.log(`   目标: ${target}`);
@@ -193,6 +212,9 @@ async function buildVersion(buildConfig) {
             throw new Error(`构建输出文件不存在: ${targetExePath}`);
         }
         fs.copyFileSync(targetExePath, output);
+        if (cliOutput) {
+            buildCliExe(target, useTargetArgForBuild, cliOutput, buildEnv);
+        }
         
         // 获取文件信息
         const stats = fs.statSync(output);
@@ -267,6 +289,7 @@ function printBuildSummary() {
     console.log('   - CMTools.x64.exe: 64位Windows便携版');
     console.log('   - CMTools.x86.exe: 32位Windows便携版');
     console.log('   - CMTools.Win7.x86.exe: Windows 7兼容便携版');
+    console.log('   - CMTools-cli.x64.exe / CMTools-cli.x86.exe: 命令行程序，供批处理与 LIMS 脚本调用');
     
     console.log('\n📚 相关文档:');
     console.log('   - VERSION_SELECTION_GUIDE.md: 版本选择指南');
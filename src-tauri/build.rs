@@ -1,9 +1,16 @@
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::Path;
 
 fn main() {
+    // 从工具清单读取需要嵌入的二进制文件列表
+    let binary_names = read_manifest_binaries();
+
     // 强制重新复制二进制文件并触发重新编译
-    force_rebuild_binaries();
+    force_rebuild_binaries(&binary_names);
+
+    // 生成嵌入二进制表
+    generate_embedded_table(&binary_names);
 
     // 为 Windows 设置环境变量（Windows 10+）
     #[cfg(target_os = "windows")]
@@ -18,9 +25,67 @@ fn main() {
     tauri_build::build()
 }
 
+/// 读取 tools.json 中声明的二进制文件名（不带扩展名）
+fn read_manifest_binaries() -> Vec<String> {
+    let manifest_path = Path::new("tools.json");
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+
+    let content = fs::read_to_string(manifest_path).expect("Failed to read tools.json");
+    let manifest: serde_json::Value =
+        serde_json::from_str(&content).expect("Failed to parse tools.json");
+
+    manifest["tools"]
+        .as_array()
+        .expect("tools.json must contain a \"tools\" array")
+        .iter()
+        .map(|tool| {
+            tool["binary"]
+                .as_str()
+                .expect("Every tool in tools.json must declare a \"binary\"")
+                .to_string()
+        })
+        .collect()
+}
+
+/// 根据目标平台返回资源目录中的二进制文件路径
+fn asset_path(assets_dir: &Path, name: &str) -> std::path::PathBuf {
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        assets_dir.join(format!("{}.exe", name))
+    } else {
+        assets_dir.join(name)
+    }
+}
+
+/// 生成 `include_bytes!` 嵌入表及各二进制文件的 SHA-256，缺失的二进制文件仅给出警告
+fn generate_embedded_table(binary_names: &[String]) {
+    let assets_dir = Path::new("../src/assets");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR not set");
+
+    let mut table = String::from(
+        "// 由 build.rs 根据 tools.json 自动生成，请勿手动修改\n\
+         // (二进制名, 文件内容, SHA-256)\n\
+         pub(crate) static EMBEDDED_BINARIES: &[(&str, &[u8], &str)] = &[\n",
+    );
+    for name in binary_names {
+        let source_path = asset_path(assets_dir, name);
+        if let Ok(absolute_path) = source_path.canonicalize() {
+            let data = fs::read(&absolute_path).expect("Failed to read embedded binary");
+            let digest = format!("{:x}", Sha256::digest(&data));
+            table.push_str(&format!(
+                "    ({:?}, include_bytes!({:?}), {:?}),\n",
+                name, absolute_path, digest
+            ));
+        }
+    }
+    table.push_str("];\n");
+
+    fs::write(Path::new(&out_dir).join("embedded_tools.rs"), table)
+        .expect("Failed to write embedded_tools.rs");
+}
+
 /// 强制重新复制二进制文件并触发重新编译
 /// 每次构建都无条件复制所有二进制文件，不使用缓存
-fn force_rebuild_binaries() {
+fn force_rebuild_binaries(binary_names: &[String]) {
     let assets_dir = Path::new("../src/assets");
 
     if !assets_dir.exists() {
@@ -28,17 +93,6 @@ fn force_rebuild_binaries() {
         return;
     }
 
-    // 定义需要监听的二进制文件列表（不带扩展名）
-    let binary_names = vec![
-        "AneuFiler",
-        "Aneu23",
-        "SMNFiler_v1",
-        "SMNFiler_v2",
-        "SHCarrier",
-        "UPDFiler_v1",
-        "UPDFiler_v2",
-    ];
-
     // 创建临时目录用于存放复制的二进制文件
     let build_bin_dir = Path::new("build_binaries");
 
@@ -51,13 +105,9 @@ fn force_rebuild_binaries() {
         return;
     }
 
-    for name in &binary_names {
-        // 根据当前平台确定文件扩展名
-        let source_path = if cfg!(target_os = "windows") {
-            assets_dir.join(format!("{}.exe", name))
-        } else {
-            assets_dir.join(name)
-        };
+    for name in binary_names {
+        // 根据目标平台确定文件扩展名
+        let source_path = asset_path(assets_dir, name);
 
         let dest_path = build_bin_dir.join(source_path.file_name().unwrap_or_default());
 
@@ -0,0 +1,798 @@
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::inputs;
+use crate::jobs::{self, EventSink, FileStatus, JobEvent};
+use crate::registry;
+use crate::settings;
+use crate::{
+    PreparedJob, PreparedPipeline, ProcessError, ProcessResult, RunOptions, list_tools, pipelines,
+    prepare_job, prepare_pipeline, process_error_to_localized_string, run_job, run_pipeline,
+};
+
+// 请求（请求头与请求体）的大小上限
+const MAX_REQUEST_BYTES: usize = 1 << 20;
+
+// 读取请求的超时时间，避免空闲连接长期占用
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 保留的已结束作业数，超出时移除最早结束的作业
+const MAX_FINISHED_JOBS: usize = 100;
+
+// 通过接口提交的作业：指定工具（tool）或已保存的流水线（pipeline）之一
+#[derive(Deserialize)]
+struct SubmitRequest {
+    #[serde(default)]
+    tool: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    pipeline: Option<String>,
+    // 输入文件或文件夹，文件夹按筛选规则递归展开
+    files: Vec<String>,
+    #[serde(default)]
+    options: Map<String, Value>,
+    #[serde(default)]
+    run: RunOptions,
+    #[serde(default)]
+    language: Option<String>,
+    // 文件夹的筛选规则，缺省时使用工具定义（流水线为第一个步骤的工具）中的默认规则
+    #[serde(default)]
+    include: Option<Vec<String>>,
+    #[serde(default)]
+    exclude: Option<Vec<String>>,
+}
+
+// 事件流中的事件：事件名及数据
+// 文件结束事件只附带序号与状态，完整结果只保存在 Progress.results 中，可通过状态或结果接口取回
+#[derive(Clone)]
+struct StreamEvent {
+    name: &'static str,
+    data: Value,
+}
+
+impl StreamEvent {
+    fn new(event: &JobEvent) -> Self {
+        let data = match event {
+            JobEvent::FileFinished {
+                job_id,
+                index,
+                result,
+            } => json!({
+                "kind": "file_finished",
+                "job_id": job_id,
+                "index": index,
+                "file_path": result.file_path,
+                "status": result.status,
+                "success": result.success,
+                "code": result.code,
+            }),
+            _ => json!(event),
+        };
+        StreamEvent {
+            name: event.name(),
+            data,
+        }
+    }
+}
+
+// 作业进度：已发出的事件、已结束文件的结果及作业是否已结束
+#[derive(Default)]
+struct Progress {
+    events: Vec<StreamEvent>,
+    // 运行中按结束顺序排列，作业结束后替换为按输入顺序排列的全部结果
+    results: Vec<ProcessResult>,
+    finished: bool,
+    finished_at: Option<u64>,
+}
+
+// 接口作业记录，作业结束后保留以便取回结果
+struct ApiJob {
+    // 流水线作业的 tool 与 version 为空
+    tool: Option<String>,
+    version: Option<String>,
+    pipeline: Option<String>,
+    // 作业处理的文件总数，流水线为步骤数 × 文件数
+    total: usize,
+    submitted_at: u64,
+    progress: watch::Sender<Progress>,
+}
+
+impl ApiJob {
+    // 作业状态摘要，include_results 为 true 时附带已结束文件的结果
+    fn status(&self, job_id: &str, include_results: bool) -> Value {
+        let progress = self.progress.borrow();
+        let count = |status: FileStatus| {
+            progress
+                .results
+                .iter()
+                .filter(|result| result.status == status)
+                .count()
+        };
+        let succeeded = progress
+            .results
+            .iter()
+            .filter(|result| result.success)
+            .count();
+        let mut status = json!({
+            "job_id": job_id,
+            "tool": self.tool,
+            "version": self.version,
+            "pipeline": self.pipeline,
+            "state": if progress.finished { "finished" } else { "running" },
+            "total": self.total,
+            "done": progress.results.len(),
+            "succeeded": succeeded,
+            "failed": count(FileStatus::Completed) - succeeded,
+            "cancelled": count(FileStatus::Cancelled),
+            "not_started": count(FileStatus::NotStarted),
+            "submitted_at": self.submitted_at,
+            "finished_at": progress.finished_at,
+        });
+        if include_results {
+            status["results"] = json!(progress.results);
+        }
+        status
+    }
+}
+
+static API_JOBS: LazyLock<Mutex<HashMap<String, Arc<ApiJob>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn find_job(job_id: &str) -> Option<Arc<ApiJob>> {
+    API_JOBS.lock().unwrap().get(job_id).cloned()
+}
+
+// 获取访问令牌：使用设置中的令牌，未设置时生成随机令牌并保存到设置
+pub(crate) fn ensure_token() -> Result<String, ProcessError> {
+    let mut current = settings::current();
+    if let Some(token) = current.api.token.as_ref().filter(|token| !token.is_empty()) {
+        return Ok(token.clone());
+    }
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| std::io::Error::other(e.to_string()))?;
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    current.api.token = Some(token.clone());
+    settings::save(current)?;
+    Ok(token)
+}
+
+// 在本机回环地址上启动接口服务，直到监听失败
+pub(crate) async fn serve(port: u16, token: String) -> Result<(), ProcessError> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+    let token = Arc::new(token);
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let token = token.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, &token).await;
+        });
+    }
+}
+
+// 取消全部运行中的接口作业并等待其结束，命令行服务退出前调用，避免遗留工具进程
+pub(crate) async fn cancel_all() {
+    let running: Vec<(String, Arc<ApiJob>)> = API_JOBS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, job)| !job.progress.borrow().finished)
+        .map(|(id, job)| (id.clone(), job.clone()))
+        .collect();
+    for (id, job) in running {
+        jobs::cancel(&id);
+        let _ = job
+            .progress
+            .subscribe()
+            .wait_for(|progress| progress.finished)
+            .await;
+    }
+}
+
+// 解析后的 HTTP 请求
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn language(&self) -> &str {
+        self.query.get("lang").map(String::as_str).unwrap_or("en")
+    }
+}
+
+// HTTP 错误响应
+struct HttpError {
+    status: u16,
+    body: Value,
+}
+
+impl HttpError {
+    fn new(status: u16, message: &str) -> Self {
+        HttpError {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+
+    fn process(status: u16, error: &ProcessError, language: &str) -> Self {
+        HttpError {
+            status,
+            body: json!({
+                "error": process_error_to_localized_string(error, language),
+                "failure": error,
+            }),
+        }
+    }
+}
+
+// 处理单个连接：每个连接只处理一个请求，响应后关闭
+async fn handle_connection(mut stream: TcpStream, token: &str) -> std::io::Result<()> {
+    let request = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request)) => request,
+        Ok(Err(e)) => return write_json(&mut stream, e.status, &e.body).await,
+        Err(_) => return Ok(()),
+    };
+
+    let segments: Vec<&str> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    // 浏览器的 EventSource 无法设置请求头，仅事件流接受 ?token= 查询参数；
+    // 其余接口只接受 Authorization 请求头，避免令牌出现在代理或工具的 URL 日志中
+    let query_token = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["jobs", _, "events"]) => request.query.get("token").map(String::as_str),
+        _ => None,
+    };
+    let authorized = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or(query_token)
+        .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()));
+    if !authorized {
+        let e = HttpError::new(401, "missing or invalid token");
+        return write_json(&mut stream, e.status, &e.body).await;
+    }
+    let response = match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["tools"]) => Ok((
+            200,
+            json!(list_tools(Some(request.language().to_string())).await),
+        )),
+        ("GET", ["pipelines"]) => Ok((200, json!(pipelines::list()))),
+        ("GET", ["jobs"]) => Ok((200, list_jobs())),
+        ("POST", ["jobs"]) => submit(&request).await,
+        ("GET", ["jobs", id]) => job_status(id),
+        ("GET", ["jobs", id, "events"]) => return stream_events(&mut stream, id).await,
+        ("POST", ["jobs", id, "cancel"]) => cancel(id),
+        ("GET", ["jobs", id, "results"]) => job_results(id),
+        ("GET", ["jobs", id, "outputs"]) => job_outputs(id),
+        _ => Err(HttpError::new(404, "not found")),
+    };
+    match response {
+        Ok((status, body)) => write_json(&mut stream, status, &body).await,
+        Err(e) => write_json(&mut stream, e.status, &e.body).await,
+    }
+}
+
+// 读取并解析请求头及 Content-Length 指定长度的请求体
+async fn read_request(stream: &mut TcpStream) -> Result<Request, HttpError> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|_| HttpError::new(400, "failed to read request"))?;
+        if read == 0 {
+            return Err(HttpError::new(400, "incomplete request"));
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if buffer.len() > MAX_REQUEST_BYTES {
+            return Err(HttpError::new(413, "request too large"));
+        }
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let header_len = match parsed.parse(&buffer) {
+            Ok(httparse::Status::Complete(len)) => len,
+            Ok(httparse::Status::Partial) => continue,
+            Err(_) => return Err(HttpError::new(400, "malformed request")),
+        };
+
+        let header = |name: &str| {
+            parsed
+                .headers
+                .iter()
+                .find(|header| header.name.eq_ignore_ascii_case(name))
+                .map(|header| String::from_utf8_lossy(header.value).to_string())
+        };
+        let content_length: usize = header("content-length")
+            .map(|value| value.trim().parse())
+            .transpose()
+            .map_err(|_| HttpError::new(400, "invalid Content-Length"))?
+            .unwrap_or(0);
+        // 避免 header_len + content_length 溢出后绕过上限
+        if content_length > MAX_REQUEST_BYTES.saturating_sub(header_len) {
+            return Err(HttpError::new(413, "request too large"));
+        }
+        let target = parsed.path.unwrap_or("/").to_string();
+        let method = parsed.method.unwrap_or_default().to_string();
+        let authorization = header("authorization");
+
+        let mut body = buffer[header_len..].to_vec();
+        while body.len() < content_length {
+            let read = stream
+                .read(&mut chunk)
+                .await
+                .map_err(|_| HttpError::new(400, "failed to read request body"))?;
+            if read == 0 {
+                return Err(HttpError::new(400, "incomplete request body"));
+            }
+            body.extend_from_slice(&chunk[..read]);
+            if body.len() > MAX_REQUEST_BYTES {
+                return Err(HttpError::new(413, "request too large"));
+            }
+        }
+        body.truncate(content_length);
+
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        return Ok(Request {
+            method,
+            path: path.to_string(),
+            query: parse_query(query),
+            authorization,
+            body,
+        });
+    }
+}
+
+// 解析查询参数（仅处理 %XX 与 + 转义）
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// 比较令牌，耗时与内容无关，避免通过响应时间猜测令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+async fn write_json(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason_phrase(status),
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await
+}
+
+// 已准备好的接口作业：单个工具的作业或流水线
+enum PreparedSubmit {
+    Job(Arc<PreparedJob>),
+    Pipeline(PreparedPipeline),
+}
+
+// POST /jobs：展开输入并启动作业或流水线，与 start_job、start_pipeline 使用相同的作业流程
+async fn submit(request: &Request) -> Result<(u16, Value), HttpError> {
+    let submit: SubmitRequest = serde_json::from_slice(&request.body)
+        .map_err(|e| HttpError::new(400, &format!("invalid request body: {}", e)))?;
+    let lang = submit
+        .language
+        .clone()
+        .unwrap_or_else(|| request.language().to_string());
+
+    // 展开文件夹（最多 10000 个文件）与准备可执行文件（缓存文件锁、释放内置工具）为阻塞操作，放到阻塞线程池中执行
+    let prepare_lang = lang.clone();
+    let (prepared, files) =
+        tokio::task::spawn_blocking(move || prepare_submit(submit, &prepare_lang))
+            .await
+            .map_err(|e| HttpError::new(500, &e.to_string()))??;
+
+    // 作业事件记录到进度中，供状态查询与事件流使用
+    let (progress, _) = watch::channel(Progress::default());
+    let sink = progress.clone();
+    let events: EventSink = Arc::new(move |event: JobEvent| {
+        sink.send_modify(|progress| {
+            progress.events.push(StreamEvent::new(&event));
+            if let JobEvent::FileFinished { result, .. } = event {
+                progress.results.push(*result);
+            }
+        });
+    });
+
+    let (tool, version, pipeline, steps) = match &prepared {
+        PreparedSubmit::Job(job) => (
+            Some(job.build.def.name.clone()),
+            Some(job.build.version.clone()),
+            None,
+            1,
+        ),
+        PreparedSubmit::Pipeline(pipeline) => (
+            None,
+            None,
+            Some(pipeline.name.clone()),
+            pipeline.steps.len(),
+        ),
+    };
+    let api_job = Arc::new(ApiJob {
+        tool,
+        version,
+        pipeline,
+        total: files.len() * steps,
+        submitted_at: now_millis(),
+        progress,
+    });
+    let job_id = match prepared {
+        PreparedSubmit::Job(job) => {
+            jobs::spawn(move |job_id, cancel| run_job(job_id, job, files, cancel, events))
+        }
+        PreparedSubmit::Pipeline(pipeline) => {
+            jobs::spawn(move |job_id, cancel| run_pipeline(job_id, pipeline, files, cancel, events))
+        }
+    };
+    register(&job_id, api_job.clone());
+
+    // 作业结束后取回按输入顺序排列的全部结果
+    let waiting_id = job_id.clone();
+    tokio::spawn(async move {
+        let results = jobs::wait(&waiting_id).await;
+        api_job.progress.send_modify(|progress| {
+            if let Some(results) = results {
+                progress.results = results;
+            }
+            progress.finished = true;
+            progress.finished_at = Some(now_millis());
+        });
+    });
+
+    Ok((202, json!({ "job_id": job_id })))
+}
+
+// 校验请求、展开输入并准备作业或流水线
+fn prepare_submit(
+    submit: SubmitRequest,
+    lang: &str,
+) -> Result<(PreparedSubmit, Vec<String>), HttpError> {
+    let pipeline = match (&submit.pipeline, &submit.tool) {
+        (Some(name), None) => Some(
+            prepare_pipeline(name, submit.run.clone(), lang)
+                .map_err(|e| HttpError::process(400, &e, lang))?,
+        ),
+        (None, Some(_)) => None,
+        _ => {
+            return Err(HttpError::new(
+                400,
+                "exactly one of tool and pipeline is required",
+            ));
+        }
+    };
+    let build = match (&pipeline, &submit.tool) {
+        (Some(pipeline), _) => pipeline.steps[0].build.clone(),
+        (None, tool) => registry::current()
+            .find(
+                tool.as_deref().unwrap_or_default(),
+                submit.version.as_deref(),
+            )
+            .map_err(|e| HttpError::process(400, &e, lang))?,
+    };
+    let filters = build
+        .def
+        .input_filters
+        .clone()
+        .overridden(submit.include, submit.exclude);
+    let files =
+        inputs::expand(&submit.files, &filters).map_err(|e| HttpError::process(400, &e, lang))?;
+    if files.is_empty() {
+        return Err(HttpError::new(400, "no input files matched"));
+    }
+
+    let prepared = match pipeline {
+        Some(pipeline) => PreparedSubmit::Pipeline(pipeline),
+        None => {
+            let job = prepare_job(
+                &build.def.name,
+                submit.version.as_deref(),
+                &submit.options,
+                submit.run,
+                lang,
+            )
+            .map_err(|e| HttpError::process(400, &e, lang))?;
+            PreparedSubmit::Job(Arc::new(job))
+        }
+    };
+    Ok((prepared, files))
+}
+
+// 记录新作业，超出保留数量时移除最早结束的作业
+fn register(job_id: &str, job: Arc<ApiJob>) {
+    let mut api_jobs = API_JOBS.lock().unwrap();
+    api_jobs.insert(job_id.to_string(), job);
+
+    let mut finished: Vec<(u64, String)> = api_jobs
+        .iter()
+        .filter_map(|(id, job)| {
+            let finished_at = job.progress.borrow().finished_at?;
+            Some((finished_at, id.clone()))
+        })
+        .collect();
+    if finished.len() > MAX_FINISHED_JOBS {
+        finished.sort();
+        for (_, id) in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+            api_jobs.remove(id);
+        }
+    }
+}
+
+fn unknown_job(job_id: &str) -> HttpError {
+    HttpError::new(404, &format!("unknown job: {}", job_id))
+}
+
+// GET /jobs：全部作业的状态摘要（按提交时间排列）
+fn list_jobs() -> Value {
+    let api_jobs = API_JOBS.lock().unwrap();
+    let mut jobs: Vec<(&String, &Arc<ApiJob>)> = api_jobs.iter().collect();
+    jobs.sort_by_key(|(_, job)| job.submitted_at);
+    json!(
+        jobs.into_iter()
+            .map(|(id, job)| job.status(id, false))
+            .collect::<Vec<_>>()
+    )
+}
+
+// GET /jobs/{id}：作业状态及已结束文件的结果
+fn job_status(job_id: &str) -> Result<(u16, Value), HttpError> {
+    let job = find_job(job_id).ok_or_else(|| unknown_job(job_id))?;
+    Ok((200, job.status(job_id, true)))
+}
+
+// POST /jobs/{id}/cancel：取消作业，未开始的文件不再处理，运行中的工具进程被终止
+fn cancel(job_id: &str) -> Result<(u16, Value), HttpError> {
+    find_job(job_id).ok_or_else(|| unknown_job(job_id))?;
+    Ok((200, json!({ "cancelled": jobs::cancel(job_id) })))
+}
+
+// GET /jobs/{id}/results：作业结束后按输入顺序返回全部结果
+fn job_results(job_id: &str) -> Result<(u16, Value), HttpError> {
+    let job = find_job(job_id).ok_or_else(|| unknown_job(job_id))?;
+    let progress = job.progress.borrow();
+    if !progress.finished {
+        return Err(HttpError::new(409, "job is still running"));
+    }
+    Ok((200, json!(progress.results)))
+}
+
+// GET /jobs/{id}/outputs：各输入文件生成的结果文件路径
+fn job_outputs(job_id: &str) -> Result<(u16, Value), HttpError> {
+    let job = find_job(job_id).ok_or_else(|| unknown_job(job_id))?;
+    let progress = job.progress.borrow();
+    let outputs: Vec<Value> = progress
+        .results
+        .iter()
+        .map(|result| {
+            json!({
+                "file_path": result.file_path,
+                "success": result.success,
+                "output_files": result.output_files,
+            })
+        })
+        .collect();
+    Ok((200, json!(outputs)))
+}
+
+// GET /jobs/{id}/events：以 Server-Sent Events 推送作业事件
+// 先补发已发生的事件，再实时推送，作业结束后关闭连接
+async fn stream_events(stream: &mut TcpStream, job_id: &str) -> std::io::Result<()> {
+    let Some(job) = find_job(job_id) else {
+        let e = unknown_job(job_id);
+        return write_json(stream, e.status, &e.body).await;
+    };
+
+    stream
+        .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n")
+        .await?;
+    let mut updates = job.progress.subscribe();
+    let mut sent = 0;
+    loop {
+        let (events, finished) = {
+            let progress = updates.borrow_and_update();
+            (progress.events[sent..].to_vec(), progress.finished)
+        };
+        sent += events.len();
+        for event in events {
+            let message = format!("event: {}\ndata: {}\n\n", event.name, event.data);
+            stream.write_all(message.as_bytes()).await?;
+        }
+        stream.flush().await?;
+        if finished || updates.changed().await.is_err() {
+            break;
+        }
+    }
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    // 在本机随机端口上启动接口服务，返回端口号
+    async fn start_server() -> u16 {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, TOKEN).await;
+                });
+            }
+        });
+        port
+    }
+
+    // 发送原始请求，返回响应状态码与响应体
+    async fn send(port: u16, request: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+            .await
+            .unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    async fn get(port: u16, target: &str, authorization: Option<&str>) -> (u16, String) {
+        let header = authorization
+            .map(|value| format!("Authorization: {}\r\n", value))
+            .unwrap_or_default();
+        send(
+            port,
+            &format!(
+                "GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+                target, header
+            ),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_token_are_rejected() {
+        let port = start_server().await;
+        let bearer = format!("Bearer {}", TOKEN);
+
+        assert_eq!(get(port, "/jobs", None).await.0, 401);
+        assert_eq!(get(port, "/jobs", Some("Bearer wrong")).await.0, 401);
+        assert_eq!(get(port, "/jobs", Some(TOKEN)).await.0, 401);
+        let (status, body) = get(port, "/jobs", Some(&bearer)).await;
+        assert_eq!(status, 200, "{}", body);
+        assert_eq!(get(port, "/unknown", Some(&bearer)).await.0, 404);
+    }
+
+    #[tokio::test]
+    async fn query_token_is_accepted_only_on_the_event_stream() {
+        let port = start_server().await;
+        let query = format!("token={}", TOKEN);
+
+        assert_eq!(get(port, &format!("/jobs?{}", query), None).await.0, 401);
+        assert_eq!(
+            get(port, &format!("/jobs/job-0/results?{}", query), None)
+                .await
+                .0,
+            401
+        );
+        assert_eq!(
+            get(port, "/jobs/job-0/events?token=wrong", None).await.0,
+            401
+        );
+        // 令牌有效，作业不存在
+        assert_eq!(
+            get(port, &format!("/jobs/job-0/events?{}", query), None)
+                .await
+                .0,
+            404
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected_before_reading() {
+        let port = start_server().await;
+        let post = |length: &str| {
+            format!(
+                "POST /jobs HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\n\r\n",
+                TOKEN, length
+            )
+        };
+
+        // 只发送请求头，服务不等待请求体即返回 413
+        let too_large = (MAX_REQUEST_BYTES + 1).to_string();
+        assert_eq!(send(port, &post(&too_large)).await.0, 413);
+        assert_eq!(send(port, &post(&usize::MAX.to_string())).await.0, 413);
+        assert_eq!(send(port, &post("abc")).await.0, 400);
+
+        let body = "{}";
+        let (status, response) = send(port, &format!("{}{}", post("2"), body)).await;
+        assert_eq!(status, 400);
+        assert!(response.contains("invalid request body"), "{}", response);
+    }
+
+    #[test]
+    fn parses_escaped_query_values() {
+        let query = parse_query("token=a%2Bb+c&lang=zh&flag");
+        assert_eq!(query.get("token").map(String::as_str), Some("a+b c"));
+        assert_eq!(query.get("lang").map(String::as_str), Some("zh"));
+        assert_eq!(query.get("flag").map(String::as_str), Some(""));
+    }
+}
@@ -0,0 +1,6 @@
+// 控制台子系统的命令行程序：与 cmtools 的命令行模式相同，但 Windows 上命令提示符与 PowerShell
+// 会等待其结束并取得退出码，适合批处理与 LIMS 脚本调用
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(cmtools_lib::run_console(args));
+}
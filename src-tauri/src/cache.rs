@@ -0,0 +1,245 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::manifest;
+use crate::paths;
+
+// 缓存目录锁文件名
+const LOCK_FILE: &str = ".lock";
+
+// 缓存条目中标记使用者的文件名前缀：.owner-<安装标识>
+const OWNER_PREFIX: &str = ".owner-";
+
+// 临时文件序号，与进程号一起保证同一目录下临时文件名唯一
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 当前安装的标识：由程序所在路径计算，同一位置的升级沿用相同标识
+static INSTALLATION_ID: LazyLock<String> = LazyLock::new(|| {
+    let exe = std::env::current_exe()
+        .and_then(|path| path.canonicalize())
+        .unwrap_or_default();
+    let digest = format!("{:x}", Sha256::digest(exe.to_string_lossy().as_bytes()));
+    digest[..16].to_string()
+});
+
+// 内置工具缓存目录：<app data>/cache/tools/<sha256>/<可执行文件名>
+// 按内容摘要存放，跨启动保留；Unix 上权限 0700 且必须归当前用户所有
+pub(crate) fn cache_dir() -> io::Result<PathBuf> {
+    let dir = paths::tool_cache_dir();
+    fs::create_dir_all(&dir)?;
+    ensure_private(&dir)?;
+    Ok(dir)
+}
+
+// 确认目录是当前用户所有的普通目录（不跟随符号链接），其他用户有权限时收紧为 0700
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn ensure_private(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        // SAFETY: getuid 总是成功且没有副作用
+        let uid = unsafe { libc::getuid() };
+
+        let meta = fs::symlink_metadata(dir)?;
+        if !meta.is_dir() || meta.uid() != uid {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "tool cache directory is not owned by the current user: {}",
+                    dir.display()
+                ),
+            ));
+        }
+        if meta.mode() & 0o077 != 0 {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    Ok(())
+}
+
+// 缓存目录锁：持有期间其他线程及其他 CMTools 实例无法修改缓存，释放时自动解锁
+pub(crate) struct CacheLock {
+    _file: File,
+}
+
+// 获取缓存目录的排他锁（阻塞等待）
+pub(crate) fn lock(dir: &Path) -> io::Result<CacheLock> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    file.lock()?;
+    Ok(CacheLock { _file: file })
+}
+
+// 缓存条目目录，并标记为当前安装所使用
+// 调用方需持有缓存锁
+pub(crate) fn entry_dir(dir: &Path, sha256: &str) -> io::Result<PathBuf> {
+    let entry = dir.join(sha256);
+    fs::create_dir_all(&entry)?;
+
+    let owner = entry.join(format!("{}{}", OWNER_PREFIX, *INSTALLATION_ID));
+    if !owner.exists() {
+        File::create(owner)?;
+    }
+    Ok(entry)
+}
+
+// 原子写入可执行文件：先写入同目录下的临时文件，再重命名为目标文件
+// 其他进程只会看到旧文件或完整的新文件
+pub(crate) fn write_executable_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = write_new_executable(&temp_path, data).and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// 创建新文件（已存在则失败）并写入数据，Unix 上仅当前用户可读写执行
+fn write_new_executable(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o700);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+// 回收当前安装不再使用的缓存条目
+// 只处理带有当前安装标记、且摘要已不在当前内置工具中的条目：移除本安装的标记，
+// 没有其他安装使用时删除整个条目；其他安装的条目不做任何改动
+pub(crate) fn collect_garbage() -> io::Result<()> {
+    let dir = cache_dir()?;
+    let _lock = lock(&dir)?;
+
+    let in_use: HashSet<&str> = manifest::tools()
+        .iter()
+        .filter_map(|tool| tool.embedded_binary())
+        .map(|(_, sha256)| sha256)
+        .collect();
+    release_unused(&dir, &in_use, &INSTALLATION_ID)
+}
+
+// 移除 installation 在摘要不属于 in_use 的条目上的标记，没有其他安装使用时删除整个条目
+// 调用方需持有缓存锁
+fn release_unused(dir: &Path, in_use: &HashSet<&str>, installation: &str) -> io::Result<()> {
+    let owner_name = format!("{}{}", OWNER_PREFIX, installation);
+
+    for entry in fs::read_dir(dir)?.flatten() {
+        let entry_path = entry.path();
+        let owner = entry_path.join(&owner_name);
+        if !entry_path.is_dir()
+            || !owner.exists()
+            || in_use.contains(entry.file_name().to_string_lossy().as_ref())
+        {
+            continue;
+        }
+
+        fs::remove_file(&owner)?;
+        let has_other_owners = fs::read_dir(&entry_path)?
+            .flatten()
+            .any(|file| file.file_name().to_string_lossy().starts_with(OWNER_PREFIX));
+        if !has_other_owners {
+            let _ = fs::remove_dir_all(&entry_path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 系统临时目录中的空测试目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cmtools-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // 创建缓存条目，包含可执行文件及各安装的使用标记
+    fn entry(dir: &Path, sha256: &str, owners: &[&str]) -> PathBuf {
+        let entry = dir.join(sha256);
+        fs::create_dir_all(&entry).unwrap();
+        fs::write(entry.join("AneuFiler"), sha256).unwrap();
+        for owner in owners {
+            File::create(entry.join(format!("{}{}", OWNER_PREFIX, owner))).unwrap();
+        }
+        entry
+    }
+
+    #[test]
+    fn garbage_collection_only_releases_this_installation() {
+        let dir = temp_dir("gc");
+        let unused = entry(&dir, "aaaa", &["me"]);
+        let shared = entry(&dir, "bbbb", &["me", "other"]);
+        let current = entry(&dir, "cccc", &["me"]);
+        let foreign = entry(&dir, "dddd", &["other"]);
+        fs::write(dir.join(LOCK_FILE), "").unwrap();
+
+        release_unused(&dir, &HashSet::from(["cccc"]), "me").unwrap();
+
+        // 仅本安装使用且已不再内置的条目被删除
+        assert!(!unused.exists());
+        // 其他安装仍在使用：只移除本安装的标记
+        assert!(shared.join("AneuFiler").exists());
+        assert!(!shared.join(".owner-me").exists());
+        assert!(shared.join(".owner-other").exists());
+        // 当前内置工具的条目与其他安装的条目保持不变
+        assert!(current.join(".owner-me").exists());
+        assert!(foreign.join(".owner-other").exists());
+        assert!(dir.join(LOCK_FILE).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cache_dir_must_be_a_private_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("private");
+        let cache = dir.join("tools");
+        fs::create_dir(&cache).unwrap();
+        fs::set_permissions(&cache, fs::Permissions::from_mode(0o755)).unwrap();
+        ensure_private(&cache).unwrap();
+        let mode = fs::metadata(&cache).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        // 指向其他位置的符号链接不被当作缓存目录
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(&cache, &link).unwrap();
+        let error = ensure_private(&link).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+
+        let file = dir.join("file");
+        fs::write(&file, "").unwrap();
+        assert!(ensure_private(&file).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
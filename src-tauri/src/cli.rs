@@ -0,0 +1,789 @@
+use serde_json::{Map, Number, Value};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::api;
+use crate::inputs;
+use crate::jobs::{self, EventSink, FileStatus, JobEvent};
+use crate::manifest::OptionKind;
+use crate::outputs::{CollisionPolicy, OutputOptions};
+use crate::paths;
+use crate::registry;
+use crate::settings;
+use crate::{
+    ProcessError, ProcessResult, RunOptions, get_tool_version, list_tools, prepare_job,
+    prepare_pipeline, process_error_to_localized_string, run_job, run_pipeline,
+};
+
+// 退出码：全部文件处理成功、部分文件处理失败、参数错误、无法启动（如未知工具、无效选项、输入不存在）
+// 及被 Ctrl+C 中断（与 shell 对 SIGINT 的约定一致）
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FILES_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_ERROR: i32 = 3;
+const EXIT_INTERRUPTED: i32 = 130;
+
+const USAGE: &str = "\
+Usage:
+  cmtools                                 Start the graphical interface
+  cmtools run --tool <NAME> [OPTIONS] <FILES OR FOLDERS>...
+                                          Process files without opening a window
+  cmtools run --pipeline <NAME> [OPTIONS] <FILES OR FOLDERS>...
+                                          Run a saved pipeline on the files
+  cmtools tools                           List tools, versions and their options
+  cmtools version --tool <NAME>           Print the version reported by a tool
+  cmtools serve [--port <PORT>]           Run the local HTTP API until Ctrl+C
+  cmtools help                            Show this help
+
+Common options:
+  --json                  Print machine-readable JSON to stdout
+  --lang <zh|en>          Language of result messages (default: en)
+  --tool-version <VER>    Run a specific tool build instead of the default
+
+Run options:
+  --pipeline <NAME>       Run every step of a saved pipeline instead of one tool
+  --<option> [VALUE]      Tool option, by short flag or key (see `cmtools tools`),
+                          e.g. --std STD --area; --no-<option> turns a bool option off
+  --option <KEY=VALUE>    Tool option by key
+  --output <DIR>          Write result files to DIR
+  --collision <POLICY>    rename | overwrite | skip when DIR already has the file
+  --sandbox               Process each file in an isolated staging directory
+  --max-parallel <N>      Maximum number of files processed at once
+  --timeout <SECS>        Per-file timeout
+  --include <PATTERNS>    Comma-separated patterns for files in folders
+  --exclude <PATTERNS>    Comma-separated patterns to skip in folders
+
+Serve options:
+  --port <PORT>           Port on 127.0.0.1 (default: api.port in settings, 17321)
+  --token <TOKEN>         Access token (default: api.token in settings, generated if unset)
+
+Exit codes:
+  0 all files succeeded, 1 some files failed, 2 invalid arguments,
+  3 could not start (unknown tool, invalid option, missing input), 130 interrupted
+";
+
+// 命令执行失败：退出码及错误消息
+#[derive(Debug)]
+struct CliError {
+    code: i32,
+    message: String,
+}
+
+impl CliError {
+    fn usage(message: impl Into<String>) -> Self {
+        CliError {
+            code: EXIT_USAGE,
+            message: message.into(),
+        }
+    }
+
+    fn process(error: &ProcessError, language: &str) -> Self {
+        CliError {
+            code: EXIT_ERROR,
+            message: process_error_to_localized_string(error, language),
+        }
+    }
+}
+
+// 解析后的命令行参数
+struct CliArgs {
+    json: bool,
+    language: String,
+    tool: Option<String>,
+    tool_version: Option<String>,
+    pipeline: Option<String>,
+    options: Map<String, Value>,
+    output: Option<PathBuf>,
+    collision: CollisionPolicy,
+    run: RunOptions,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    inputs: Vec<String>,
+    port: Option<u16>,
+    token: Option<String>,
+}
+
+// 子命令及帮助、版本参数
+const COMMANDS: &[&str] = &[
+    "run",
+    "tools",
+    "version",
+    "serve",
+    "help",
+    "--help",
+    "-h",
+    "--version",
+    "-V",
+];
+
+// 命令行模式入口，返回进程退出码
+// 第一个参数不是子命令时（未带参数、文件关联或快捷方式传入的文件路径、macOS 附加的 -psn_ 参数等）返回 None，由调用方启动图形界面
+pub fn run_cli(args: Vec<String>) -> Option<i32> {
+    let command = args.first()?;
+    if !COMMANDS.contains(&command.as_str()) {
+        return None;
+    }
+
+    #[cfg(windows)]
+    attach_console();
+
+    Some(run_command_line(args))
+}
+
+// 控制台程序 cmtools-cli 的入口：Windows 上命令提示符与 PowerShell 会等待控制台程序结束并取得退出码
+// 不是子命令时输出错误而不启动图形界面
+pub fn run_console(args: Vec<String>) -> i32 {
+    match args.first() {
+        Some(command) if COMMANDS.contains(&command.as_str()) => run_command_line(args),
+        Some(command) => {
+            eprintln!("error: unknown command: {}", command);
+            eprintln!("Run `cmtools-cli help` for usage.");
+            EXIT_USAGE
+        }
+        None => {
+            eprint!("{}", USAGE);
+            EXIT_USAGE
+        }
+    }
+}
+
+// 执行子命令，返回进程退出码
+fn run_command_line(args: Vec<String>) -> i32 {
+    let command = args[0].clone();
+    match command.as_str() {
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            EXIT_SUCCESS
+        }
+        "--version" | "-V" => {
+            println!("cmtools {}", env!("CARGO_PKG_VERSION"));
+            EXIT_SUCCESS
+        }
+        "run" | "tools" | "version" | "serve" => {
+            let runtime = match tokio::runtime::Runtime::new() {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    return EXIT_ERROR;
+                }
+            };
+            let result = runtime.block_on(async move {
+                let args = parse_args(args[1..].to_vec())?;
+                match command.as_str() {
+                    "run" => run_command(args).await,
+                    "tools" => tools_command(args).await,
+                    "serve" => serve_command(args).await,
+                    _ => version_command(args).await,
+                }
+            });
+            match result {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("error: {}", e.message);
+                    if e.code == EXIT_USAGE {
+                        eprintln!("Run `cmtools help` for usage.");
+                    }
+                    e.code
+                }
+            }
+        }
+        other => {
+            eprintln!("error: unknown command: {}", other);
+            eprintln!("Run `cmtools help` for usage.");
+            EXIT_USAGE
+        }
+    }
+}
+
+// 发布版为 Windows 图形子系统程序，没有控制台；从命令行启动时附加到父进程的控制台以便输出
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+// 解析参数；工具选项依据 --tool 指定工具的选项定义解释，因此先找出工具再逐个解析
+fn parse_args(args: Vec<String>) -> Result<CliArgs, CliError> {
+    let mut parsed = CliArgs {
+        json: false,
+        language: "en".to_string(),
+        tool: find_flag(&args, "tool"),
+        tool_version: find_flag(&args, "tool-version"),
+        pipeline: None,
+        options: Map::new(),
+        output: None,
+        collision: CollisionPolicy::default(),
+        run: RunOptions::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        inputs: Vec::new(),
+        port: None,
+        token: None,
+    };
+    let language = find_flag(&args, "lang").unwrap_or_else(|| parsed.language.clone());
+    let build = match &parsed.tool {
+        Some(tool) => Some(
+            registry::current()
+                .find(tool, parsed.tool_version.as_deref())
+                .map_err(|e| CliError::process(&e, &language))?,
+        ),
+        None => None,
+    };
+    let def = build.as_ref().map(|build| &build.def);
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            parsed.inputs.extend(args.by_ref());
+            break;
+        }
+        let Some(flag) = arg.strip_prefix("--") else {
+            parsed.inputs.push(arg);
+            continue;
+        };
+        let (name, inline) = match flag.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (flag.to_string(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError::usage(format!("--{} requires a value", name)))
+        };
+
+        match name.as_str() {
+            "json" => parsed.json = true,
+            "lang" => parsed.language = value()?,
+            "pipeline" => parsed.pipeline = Some(value()?),
+            "tool" | "tool-version" => {
+                value()?;
+            }
+            "output" => parsed.output = Some(PathBuf::from(value()?)),
+            "collision" => {
+                parsed.collision = serde_json::from_value(Value::String(value()?))
+                    .map_err(|_| CliError::usage("--collision must be rename, overwrite or skip"))?
+            }
+            "sandbox" => parsed.run.sandbox = true,
+            "max-parallel" => {
+                parsed.run.limits.max_parallel = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| CliError::usage("--max-parallel must be a number"))?,
+                )
+            }
+            "timeout" => {
+                parsed.run.limits.timeout_secs = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| CliError::usage("--timeout must be a number of seconds"))?,
+                )
+            }
+            "port" => {
+                parsed.port = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| CliError::usage("--port must be a port number"))?,
+                )
+            }
+            "token" => parsed.token = Some(value()?),
+            "include" => parsed.include.extend(split_patterns(&value()?)),
+            "exclude" => parsed.exclude.extend(split_patterns(&value()?)),
+            "option" => {
+                let text = value()?;
+                let (key, text) = text
+                    .split_once('=')
+                    .ok_or_else(|| CliError::usage("--option must be KEY=VALUE"))?;
+                let def = def.ok_or_else(|| CliError::usage("--option requires --tool"))?;
+                let option = def
+                    .options
+                    .iter()
+                    .find(|option| option.key == key)
+                    .ok_or_else(|| {
+                        CliError::usage(format!("unknown option for {}: {}", def.name, key))
+                    })?;
+                let value = option_value(option.kind, &option.key, Some(text.to_string()))?;
+                parsed.options.insert(option.key.clone(), value);
+            }
+            _ => {
+                let def =
+                    def.ok_or_else(|| CliError::usage(format!("unknown flag: --{}", name)))?;
+                // --no-<选项> 关闭布尔选项
+                let negated = name
+                    .strip_prefix("no-")
+                    .and_then(|flag| def.cli_option(flag))
+                    .filter(|option| option.kind == OptionKind::Bool && inline.is_none());
+                let (option, value) = match (def.cli_option(&name), negated) {
+                    (Some(option), _) => {
+                        let text = match option.kind {
+                            OptionKind::Bool => inline.clone(),
+                            _ => Some(value()?),
+                        };
+                        (option, option_value(option.kind, &option.key, text)?)
+                    }
+                    (None, Some(option)) => (option, Value::Bool(false)),
+                    (None, None) => {
+                        return Err(CliError::usage(format!(
+                            "unknown flag for {}: --{}",
+                            def.name, name
+                        )));
+                    }
+                };
+                parsed.options.insert(option.key.clone(), value);
+            }
+        }
+    }
+
+    if let Some(dir) = &parsed.output {
+        parsed.run.output = Some(OutputOptions {
+            dir: dir.clone(),
+            collision: parsed.collision,
+        });
+    }
+    Ok(parsed)
+}
+
+// 在 -- 之前查找参数值（--name value 或 --name=value）
+fn find_flag(args: &[String], name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let prefix = format!("--{}=", name);
+    let mut args = args.iter().take_while(|arg| *arg != "--");
+    while let Some(arg) = args.next() {
+        if *arg == flag {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn split_patterns(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// 将参数文本转换为选项值，取值范围由 resolve_options 校验
+fn option_value(kind: OptionKind, key: &str, text: Option<String>) -> Result<Value, CliError> {
+    let invalid = |expected: &str| CliError::usage(format!("{} expects {}", key, expected));
+    match kind {
+        OptionKind::Bool => match text.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("true" | "1" | "yes") => Ok(Value::Bool(true)),
+            Some("false" | "0" | "no") => Ok(Value::Bool(false)),
+            _ => Err(invalid("true or false")),
+        },
+        OptionKind::String => Ok(Value::String(text.unwrap_or_default())),
+        OptionKind::Number => {
+            let text = text.unwrap_or_default();
+            match text.parse::<i64>() {
+                Ok(number) => Ok(Value::from(number)),
+                Err(_) => text
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| invalid("a number")),
+            }
+        }
+    }
+}
+
+fn require_tool(args: &CliArgs) -> Result<String, CliError> {
+    args.tool
+        .clone()
+        .ok_or_else(|| CliError::usage("--tool is required"))
+}
+
+// 输出 JSON 到标准输出；读取方提前关闭管道时忽略写入错误
+fn print_json(value: &impl serde::Serialize) {
+    if let Ok(text) = serde_json::to_string_pretty(value) {
+        let _ = writeln!(std::io::stdout(), "{}", text);
+    }
+}
+
+// cmtools run：展开输入（文件夹按筛选规则递归展开）后以与界面相同的方式运行作业或流水线
+async fn run_command(args: CliArgs) -> Result<i32, CliError> {
+    let lang = args.language.as_str();
+    if args.pipeline.is_some() && args.tool.is_some() {
+        return Err(CliError::usage("--pipeline cannot be combined with --tool"));
+    }
+    if args.pipeline.is_none() {
+        require_tool(&args)?;
+    }
+    if args.inputs.is_empty() {
+        return Err(CliError::usage("no input files or folders given"));
+    }
+
+    let pipeline = match &args.pipeline {
+        Some(name) => Some(
+            prepare_pipeline(name, args.run.clone(), lang)
+                .map_err(|e| CliError::process(&e, lang))?,
+        ),
+        None => None,
+    };
+    let build = match &pipeline {
+        Some(pipeline) => pipeline.steps[0].build.clone(),
+        None => registry::current()
+            .find(&require_tool(&args)?, args.tool_version.as_deref())
+            .map_err(|e| CliError::process(&e, lang))?,
+    };
+    // 命令行未指定的筛选规则使用工具定义（流水线为第一个步骤的工具）中的默认规则
+    let filters = build.def.input_filters.clone().overridden(
+        (!args.include.is_empty()).then(|| args.include.clone()),
+        (!args.exclude.is_empty()).then(|| args.exclude.clone()),
+    );
+    // 相对路径按当前目录转换为绝对路径：工具在输入文件所在目录中运行，相对路径会被解析到错误的位置
+    let input_paths = args
+        .inputs
+        .iter()
+        .map(|input| {
+            std::path::absolute(input)
+                .map(|path| path.display().to_string())
+                .map_err(|e| CliError::process(&e.into(), lang))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let files = inputs::expand(&input_paths, &filters).map_err(|e| CliError::process(&e, lang))?;
+    if files.is_empty() {
+        return Err(CliError {
+            code: EXIT_ERROR,
+            message: "no input files matched".to_string(),
+        });
+    }
+
+    // 非 JSON 模式下在标准错误输出中逐个显示已完成的文件，流水线的每个步骤各处理一遍全部文件
+    let total = files.len() * pipeline.as_ref().map_or(1, |pipeline| pipeline.steps.len());
+    let events: EventSink = if args.json {
+        Arc::new(|_| {})
+    } else {
+        let done = AtomicUsize::new(0);
+        Arc::new(move |event: JobEvent| {
+            if let JobEvent::FileFinished { result, .. } = event {
+                let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                eprintln!("[{}/{}] {}", done, total, result_line(&result));
+            }
+        })
+    };
+    let job_id = match pipeline {
+        Some(pipeline) => {
+            jobs::spawn(move |job_id, cancel| run_pipeline(job_id, pipeline, files, cancel, events))
+        }
+        None => {
+            let job = prepare_job(
+                &build.def.name,
+                args.tool_version.as_deref(),
+                &args.options,
+                args.run,
+                lang,
+            )
+            .map_err(|e| CliError::process(&e, lang))?;
+            let job = Arc::new(job);
+            jobs::spawn(move |job_id, cancel| run_job(job_id, job, files, cancel, events))
+        }
+    };
+
+    // Ctrl+C 时取消作业：终止运行中的工具进程，未开始的文件不再处理
+    let (results, interrupted) = tokio::select! {
+        results = jobs::wait(&job_id) => (results.unwrap_or_default(), false),
+        _ = tokio::signal::ctrl_c() => {
+            jobs::cancel(&job_id);
+            (jobs::wait(&job_id).await.unwrap_or_default(), true)
+        }
+    };
+
+    if args.json {
+        print_json(&results);
+    } else {
+        for result in &results {
+            println!("{}", result_line(result));
+            for file in &result.output_files {
+                println!("    -> {}", file);
+            }
+        }
+        let succeeded = results.iter().filter(|result| result.success).count();
+        println!(
+            "{} succeeded, {} failed, {} total",
+            succeeded,
+            results.len() - succeeded,
+            results.len()
+        );
+    }
+
+    let incomplete = results
+        .iter()
+        .any(|result| result.status != FileStatus::Completed);
+    Ok(if interrupted || incomplete {
+        EXIT_INTERRUPTED
+    } else if results.iter().all(|result| result.success) {
+        EXIT_SUCCESS
+    } else {
+        EXIT_FILES_FAILED
+    })
+}
+
+// 单个文件结果的文本：状态、消息及错误详情
+fn result_line(result: &ProcessResult) -> String {
+    let status = match (result.status, result.success) {
+        (FileStatus::Completed, true) => "OK  ",
+        (FileStatus::Completed, false) => "FAIL",
+        _ => "SKIP",
+    };
+    // 流水线的结果附带步骤的工具名称
+    let message = match (&result.tool_name, result.step) {
+        (Some(tool), Some(_)) => format!("[{}] {}", tool, result.message),
+        _ => result.message.clone(),
+    };
+    match &result.error {
+        Some(error) => format!("{} {} - {}", status, message, error),
+        None => format!("{} {}", status, message),
+    }
+}
+
+// cmtools tools：列出工具、版本及可用的命令行选项
+async fn tools_command(args: CliArgs) -> Result<i32, CliError> {
+    let tools = list_tools(Some(args.language.clone())).await;
+    if args.json {
+        print_json(&tools);
+        return Ok(EXIT_SUCCESS);
+    }
+
+    for tool in &tools {
+        println!(
+            "{} {} (versions: {})",
+            tool.name,
+            tool.version.as_deref().unwrap_or("-"),
+            tool.versions.join(", ")
+        );
+        if let Some(description) = tool.description.get(&args.language) {
+            println!("    {}", description);
+        }
+        for option in &tool.options {
+            let mut flags = Vec::new();
+            if let Some(cli_flag) = &option.cli_flag {
+                flags.push(format!("--{}", cli_flag));
+            }
+            flags.push(format!("--{}", option.key.replace('_', "-")));
+            let kind = match option.kind {
+                OptionKind::Bool => "bool",
+                OptionKind::String => "string",
+                OptionKind::Number => "number",
+            };
+            println!(
+                "    {:<40} {} (default: {})",
+                flags.join(", "),
+                kind,
+                option.default
+            );
+        }
+    }
+    Ok(EXIT_SUCCESS)
+}
+
+// cmtools version：输出工具 --version 的结果
+async fn version_command(args: CliArgs) -> Result<i32, CliError> {
+    let tool = require_tool(&args)?;
+    let version = get_tool_version(
+        tool.clone(),
+        args.tool_version.clone(),
+        Some(args.language.clone()),
+    )
+    .await
+    .map_err(|message| CliError {
+        code: EXIT_ERROR,
+        message,
+    })?;
+
+    if args.json {
+        print_json(&serde_json::json!({
+            "tool": tool,
+            "build": args.tool_version,
+            "version": version,
+        }));
+    } else {
+        println!("{}", version);
+    }
+    Ok(EXIT_SUCCESS)
+}
+
+// cmtools serve：在本机回环地址上运行 HTTP 接口服务，Ctrl+C 时取消运行中的作业后退出
+async fn serve_command(args: CliArgs) -> Result<i32, CliError> {
+    let lang = args.language.as_str();
+    let port = args.port.unwrap_or(settings::current().api.port);
+    let token = match args.token {
+        Some(token) => token,
+        None => api::ensure_token().map_err(|e| CliError::process(&e, lang))?,
+    };
+
+    eprintln!("CMTools API listening on http://127.0.0.1:{}", port);
+    eprintln!(
+        "Send requests with \"Authorization: Bearer <token>\" (api.token in {})",
+        paths::settings_file().display()
+    );
+    tokio::select! {
+        result = api::serve(port, token) => result.map_err(|e| CliError::process(&e, lang))?,
+        _ = tokio::signal::ctrl_c() => {
+            api::cancel_all().await;
+        }
+    }
+    Ok(EXIT_SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, CliError> {
+        parse_args(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    fn usage_error(args: &[&str]) -> String {
+        match parse(args) {
+            Err(e) => {
+                assert_eq!(e.code, EXIT_USAGE, "{}", e.message);
+                e.message
+            }
+            Ok(_) => panic!("expected usage error for {:?}", args),
+        }
+    }
+
+    #[test]
+    fn parses_tool_options_by_flag_and_key() {
+        let args = parse(&[
+            "--tool", "Aneu23", "--area", "--std", "QC1", "a.txt", "--json", "b.txt",
+        ])
+        .unwrap();
+        assert_eq!(args.tool.as_deref(), Some("Aneu23"));
+        assert!(args.json);
+        assert_eq!(args.inputs, vec!["a.txt", "b.txt"]);
+        assert_eq!(args.options.get("use_area_data"), Some(&json!(true)));
+        assert_eq!(args.options.get("std_sample_name"), Some(&json!("QC1")));
+
+        let args = parse(&[
+            "--tool=SMNFiler_v2",
+            "--option",
+            "std_sample_name=S",
+            "--no-gbk",
+        ])
+        .unwrap();
+        assert_eq!(args.options.get("std_sample_name"), Some(&json!("S")));
+        assert_eq!(
+            args.options.get("windows_optimization"),
+            Some(&json!(false))
+        );
+    }
+
+    #[test]
+    fn parses_bool_and_number_values() {
+        let args = parse(&["--tool", "STR-Matcher", "--tolerance", "2", "--gbk=no"]).unwrap();
+        assert_eq!(args.options.get("tolerance"), Some(&json!(2)));
+        assert_eq!(
+            args.options.get("windows_optimization"),
+            Some(&json!(false))
+        );
+
+        let args = parse(&["--tool", "STR-Matcher", "--tolerance=0.5"]).unwrap();
+        assert_eq!(args.options.get("tolerance"), Some(&json!(0.5)));
+
+        assert_eq!(
+            usage_error(&["--tool", "STR-Matcher", "--tolerance", "abc"]),
+            "tolerance expects a number"
+        );
+        assert_eq!(
+            usage_error(&["--tool", "AneuFiler", "--area=maybe"]),
+            "use_area_data expects true or false"
+        );
+    }
+
+    #[test]
+    fn parses_run_options() {
+        let args = parse(&[
+            "--tool",
+            "AneuFiler",
+            "--output",
+            "out",
+            "--collision",
+            "skip",
+            "--sandbox",
+            "--max-parallel",
+            "2",
+            "--timeout=30",
+            "--include",
+            "*.txt, *.csv",
+            "--exclude",
+            "*_result*",
+            "--lang",
+            "zh",
+            "in",
+        ])
+        .unwrap();
+        let output = args.run.output.as_ref().unwrap();
+        assert_eq!(output.dir, PathBuf::from("out"));
+        assert_eq!(output.collision, CollisionPolicy::Skip);
+        assert!(args.run.sandbox);
+        assert_eq!(args.run.limits.max_parallel, Some(2));
+        assert_eq!(args.run.limits.timeout_secs, Some(30));
+        assert_eq!(args.include, vec!["*.txt", "*.csv"]);
+        assert_eq!(args.exclude, vec!["*_result*"]);
+        assert_eq!(args.language, "zh");
+        assert_eq!(args.inputs, vec!["in"]);
+    }
+
+    #[test]
+    fn double_dash_ends_flags() {
+        let args = parse(&["--tool", "AneuFiler", "--", "--area", "-x.txt"]).unwrap();
+        assert_eq!(args.inputs, vec!["--area", "-x.txt"]);
+        assert!(args.options.is_empty());
+        assert_eq!(
+            find_flag(&["--".into(), "--tool".into(), "X".into()], "tool"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert_eq!(usage_error(&["--area"]), "unknown flag: --area");
+        assert_eq!(
+            usage_error(&["--tool", "AneuFiler", "--std", "S"]),
+            "unknown flag for AneuFiler: --std"
+        );
+        assert_eq!(
+            usage_error(&["--tool", "AneuFiler", "--max-parallel", "many"]),
+            "--max-parallel must be a number"
+        );
+        assert_eq!(
+            usage_error(&["--collision", "merge"]),
+            "--collision must be rename, overwrite or skip"
+        );
+        assert_eq!(usage_error(&["--output"]), "--output requires a value");
+        assert_eq!(
+            usage_error(&["--option", "a=b"]),
+            "--option requires --tool"
+        );
+        assert!(matches!(
+            parse(&["--tool", "NoSuchTool"]),
+            Err(CliError {
+                code: EXIT_ERROR,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn unknown_first_argument_starts_the_gui() {
+        assert_eq!(run_cli(Vec::new()), None);
+        assert_eq!(run_cli(vec!["/data/S01.txt".to_string()]), None);
+        assert_eq!(run_cli(vec!["-psn_0_12345".to_string()]), None);
+    }
+}
@@ -0,0 +1,327 @@
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ProcessError;
+
+// 展开目录得到的文件数上限，避免误选磁盘根目录等超大目录时长时间扫描
+const MAX_EXPANDED_FILES: usize = 10_000;
+
+// 匹配不区分大小写（仪器导出文件的扩展名大小写不一），* 不跨越目录
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+// 展开目录时的文件筛选规则，如包含 *.txt、排除 *_result*
+// 不含 / 的模式匹配文件名，含 / 的模式匹配相对于所选目录的路径（可用 ** 匹配多级目录）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct InputFilters {
+    // 包含规则，为空时包含全部文件
+    pub include: Vec<String>,
+    // 排除规则，优先于包含规则
+    pub exclude: Vec<String>,
+}
+
+impl InputFilters {
+    // 以调用方指定的规则替换对应部分，未指定的部分保留（通常为工具定义中的默认规则）
+    pub fn overridden(self, include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Self {
+        InputFilters {
+            include: include.unwrap_or(self.include),
+            exclude: exclude.unwrap_or(self.exclude),
+        }
+    }
+}
+
+// 编译后的单条规则
+struct Rule {
+    pattern: Pattern,
+    // 是否匹配相对路径（否则匹配文件名）
+    match_path: bool,
+}
+
+impl Rule {
+    fn compile(pattern: &str) -> Result<Self, ProcessError> {
+        let compiled = Pattern::new(pattern).map_err(|e| ProcessError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.msg.to_string(),
+        })?;
+        Ok(Rule {
+            pattern: compiled,
+            match_path: pattern.contains('/'),
+        })
+    }
+
+    fn matches(&self, name: &str, relative: &str) -> bool {
+        let target = if self.match_path { relative } else { name };
+        self.pattern.matches_with(target, MATCH_OPTIONS)
+    }
+}
+
+struct Rules {
+    include: Vec<Rule>,
+    exclude: Vec<Rule>,
+}
+
+impl Rules {
+    fn compile(filters: &InputFilters) -> Result<Self, ProcessError> {
+        let compile_all = |patterns: &[String]| {
+            patterns
+                .iter()
+                .filter(|pattern| !pattern.trim().is_empty())
+                .map(|pattern| Rule::compile(pattern.trim()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Rules {
+            include: compile_all(&filters.include)?,
+            exclude: compile_all(&filters.exclude)?,
+        })
+    }
+
+    fn accepts(&self, name: &str, relative: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|rule| rule.matches(name, relative)))
+            && !self.exclude.iter().any(|rule| rule.matches(name, relative))
+    }
+}
+
+// 是否为应跳过的条目：隐藏文件/目录及 Office 打开文件时生成的 ~$ 锁文件
+fn is_ignored(name: &str) -> bool {
+    name.starts_with('.') || name.starts_with("~$")
+}
+
+// 展开输入路径：文件原样保留，目录递归展开为其中符合筛选规则的文件（同一目录内按名称排序）
+// 结果去重并保持输入顺序；路径不存在、规则无效或文件数超出上限时报错
+pub(crate) fn expand(
+    paths: &[String],
+    filters: &InputFilters,
+) -> Result<Vec<String>, ProcessError> {
+    let rules = Rules::compile(filters)?;
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+
+    for path in paths {
+        let root = Path::new(path);
+        if root.is_dir() {
+            walk(root, &rules, &mut |file| {
+                let file = file.display().to_string();
+                if seen.insert(file.clone()) {
+                    files.push(file);
+                }
+                files.len() <= MAX_EXPANDED_FILES
+            })?;
+        } else if root.is_file() {
+            if seen.insert(path.clone()) {
+                files.push(path.clone());
+            }
+        } else {
+            return Err(ProcessError::FileNotFound { file: path.clone() });
+        }
+
+        if files.len() > MAX_EXPANDED_FILES {
+            return Err(ProcessError::TooManyInputFiles {
+                limit: MAX_EXPANDED_FILES,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+// 列出目录中符合筛选规则的全部文件，不受 MAX_EXPANDED_FILES 限制，供监视文件夹扫描使用
+// 长期监视的文件夹会不断积累文件，超出上限后仍需发现新导出的文件
+pub(crate) fn list_dir(root: &Path, filters: &InputFilters) -> Result<Vec<String>, ProcessError> {
+    let rules = Rules::compile(filters)?;
+    let mut files = Vec::new();
+    walk(root, &rules, &mut |file| {
+        files.push(file.display().to_string());
+        true
+    })?;
+    Ok(files)
+}
+
+// 深度优先遍历目录，不跟随指向目录的符号链接以免循环
+// 无法读取的子目录被跳过；visit 返回 false 时停止遍历
+fn walk(
+    root: &Path,
+    rules: &Rules,
+    visit: &mut dyn FnMut(PathBuf) -> bool,
+) -> Result<(), ProcessError> {
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+
+    while let Some((dir, prefix)) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == root => return Err(e.into()),
+            Err(_) => continue,
+        };
+        let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if is_ignored(&name) {
+                continue;
+            }
+            let relative = format!("{}{}", prefix, name);
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                subdirs.push((entry.path(), format!("{}/", relative)));
+            } else if (file_type.is_file() || entry.path().is_file())
+                && rules.accepts(&name, &relative)
+                && !visit(entry.path())
+            {
+                return Ok(());
+            }
+        }
+
+        // 逆序入栈，使子目录按名称顺序处理
+        pending.extend(subdirs.into_iter().rev());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 在系统临时目录中创建测试目录树，结束时删除
+    struct TempTree(PathBuf);
+
+    impl TempTree {
+        fn new(name: &str, files: &[&str]) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "cmtools-inputs-{}-{}",
+                std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&root);
+            for file in files {
+                let path = root.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, "").unwrap();
+            }
+            TempTree(root)
+        }
+
+        fn root(&self) -> String {
+            self.0.display().to_string()
+        }
+
+        // 展开结果相对于根目录的路径
+        fn expand(&self, include: &[&str], exclude: &[&str]) -> Vec<String> {
+            let filters = InputFilters {
+                include: include.iter().map(|p| p.to_string()).collect(),
+                exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            };
+            expand(&[self.root()], &filters)
+                .unwrap()
+                .iter()
+                .map(|file| {
+                    Path::new(file)
+                        .strip_prefix(&self.0)
+                        .unwrap()
+                        .display()
+                        .to_string()
+                        .replace('\\', "/")
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const FILES: &[&str] = &[
+        "b.txt",
+        "a.CSV",
+        "a_result.txt",
+        "notes.docx",
+        ".hidden.txt",
+        "~$lock.txt",
+        "plate1/c.txt",
+        "plate1/raw/d.txt",
+    ];
+
+    #[test]
+    fn include_and_exclude_by_file_name() {
+        let tree = TempTree::new("names", FILES);
+        assert_eq!(
+            tree.expand(&["*.txt", "*.csv"], &["*_result*"]),
+            vec!["a.CSV", "b.txt", "plate1/c.txt", "plate1/raw/d.txt"]
+        );
+    }
+
+    #[test]
+    fn empty_include_keeps_everything_but_ignored_files() {
+        let tree = TempTree::new("all", FILES);
+        assert_eq!(
+            tree.expand(&[], &[]),
+            vec![
+                "a.CSV",
+                "a_result.txt",
+                "b.txt",
+                "notes.docx",
+                "plate1/c.txt",
+                "plate1/raw/d.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn path_patterns_match_relative_paths() {
+        let tree = TempTree::new("paths", FILES);
+        assert_eq!(tree.expand(&["plate1/*.txt"], &[]), vec!["plate1/c.txt"]);
+        assert_eq!(
+            tree.expand(&["plate1/**/*.txt"], &[]),
+            vec!["plate1/c.txt", "plate1/raw/d.txt"]
+        );
+        assert_eq!(
+            tree.expand(&["*.txt"], &["plate1/raw/*"]),
+            vec!["a_result.txt", "b.txt", "plate1/c.txt"]
+        );
+    }
+
+    #[test]
+    fn files_are_kept_and_duplicates_removed() {
+        let tree = TempTree::new("dedup", FILES);
+        let file = tree.0.join("notes.docx").display().to_string();
+        let filters = InputFilters {
+            include: vec!["*.txt".to_string()],
+            exclude: Vec::new(),
+        };
+        let files = expand(&[file.clone(), tree.root(), file.clone()], &filters).unwrap();
+        assert_eq!(files[0], file);
+        assert_eq!(files.iter().filter(|f| **f == file).count(), 1);
+        assert_eq!(files.len(), 5);
+    }
+
+    #[test]
+    fn invalid_pattern_and_missing_path_are_errors() {
+        let tree = TempTree::new("errors", FILES);
+        let filters = InputFilters {
+            include: vec!["[".to_string()],
+            exclude: Vec::new(),
+        };
+        assert!(matches!(
+            expand(&[tree.root()], &filters),
+            Err(ProcessError::InvalidPattern { .. })
+        ));
+        let missing = tree.0.join("missing.txt").display().to_string();
+        assert!(matches!(
+            expand(&[missing], &InputFilters::default()),
+            Err(ProcessError::FileNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedMutexGuard, watch};
+
+use crate::ProcessResult;
+
+// 作业中单个文件的执行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FileStatus {
+    // 工具已运行结束（无论成功与否）
+    Completed,
+    // 运行中被取消，工具进程已终止
+    Cancelled,
+    // 作业取消时尚未开始
+    NotStarted,
+}
+
+// 作业进度事件，由作业执行过程按顺序发出
+// 事件与界面框架无关，图形界面将其转发为 Tauri 事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum JobEvent {
+    // 作业开始
+    JobStarted {
+        job_id: String,
+        tool: String,
+        version: String,
+        total: usize,
+    },
+    // 文件开始处理（已获得并发许可）
+    FileStarted {
+        job_id: String,
+        index: usize,
+        file_path: String,
+    },
+    // 文件处理结束，附带该文件的结果
+    FileFinished {
+        job_id: String,
+        index: usize,
+        result: Box<ProcessResult>,
+    },
+    // 作业结束，附带统计
+    JobFinished {
+        job_id: String,
+        total: usize,
+        succeeded: usize,
+        failed: usize,
+        cancelled: usize,
+        not_started: usize,
+    },
+}
+
+impl JobEvent {
+    // 对应的 Tauri 事件名
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEvent::JobStarted { .. } => "job-started",
+            JobEvent::FileStarted { .. } => "job-file-started",
+            JobEvent::FileFinished { .. } => "job-file-finished",
+            JobEvent::JobFinished { .. } => "job-finished",
+        }
+    }
+
+    // 根据全部结果生成作业结束事件
+    pub fn finished(job_id: &str, results: &[ProcessResult]) -> Self {
+        let count = |status: FileStatus| results.iter().filter(|r| r.status == status).count();
+        let succeeded = results.iter().filter(|r| r.success).count();
+        JobEvent::JobFinished {
+            job_id: job_id.to_string(),
+            total: results.len(),
+            succeeded,
+            failed: count(FileStatus::Completed) - succeeded,
+            cancelled: count(FileStatus::Cancelled),
+            not_started: count(FileStatus::NotStarted),
+        }
+    }
+}
+
+// 作业事件的接收方
+pub(crate) type EventSink = Arc<dyn Fn(JobEvent) + Send + Sync>;
+
+// 取消令牌，作业内的各文件任务共享
+#[derive(Clone)]
+pub(crate) struct CancelToken(watch::Receiver<bool>);
+
+impl CancelToken {
+    // 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    // 等待取消请求；作业已结束、不会再被取消时一直挂起
+    pub async fn cancelled(&mut self) {
+        if self.0.wait_for(|cancelled| *cancelled).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    // 测试用：不属于任何作业的令牌及其取消请求的发送端
+    #[cfg(test)]
+    pub fn channel() -> (watch::Sender<bool>, CancelToken) {
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        (cancel_tx, CancelToken(cancel_rx))
+    }
+}
+
+// 正在运行或等待取回结果的作业
+struct JobEntry {
+    cancel: watch::Sender<bool>,
+    done: watch::Receiver<Option<Arc<Vec<ProcessResult>>>>,
+}
+
+static JOBS: LazyLock<Mutex<HashMap<String, JobEntry>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 作业序号，与启动时间一起生成唯一的作业 ID
+static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 生成作业 ID
+fn next_job_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    format!(
+        "job-{}-{}",
+        millis,
+        JOB_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// 在后台启动作业并立即返回作业 ID
+pub(crate) fn spawn<F, Fut>(run: F) -> String
+where
+    F: FnOnce(String, CancelToken) -> Fut,
+    Fut: Future<Output = Vec<ProcessResult>> + Send + 'static,
+{
+    let id = next_job_id();
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    let (done_tx, done_rx) = watch::channel(None);
+
+    JOBS.lock().unwrap().insert(
+        id.clone(),
+        JobEntry {
+            cancel: cancel_tx,
+            done: done_rx,
+        },
+    );
+
+    let job = run(id.clone(), CancelToken(cancel_rx));
+    tokio::spawn(async move {
+        let results = job.await;
+        let _ = done_tx.send(Some(Arc::new(results)));
+    });
+
+    id
+}
+
+// 请求取消作业，作业不存在或已结束时返回 false
+pub(crate) fn cancel(id: &str) -> bool {
+    let jobs = JOBS.lock().unwrap();
+    match jobs.get(id) {
+        Some(job) if job.done.borrow().is_none() => {
+            let _ = job.cancel.send(true);
+            true
+        }
+        _ => false,
+    }
+}
+
+// 等待作业结束并取回结果，取回后作业记录即被移除
+// 作业不存在（或结果已被取回）时返回 None
+pub(crate) async fn wait(id: &str) -> Option<Vec<ProcessResult>> {
+    let mut done = JOBS.lock().unwrap().get(id)?.done.clone();
+    let results = done
+        .wait_for(|results| results.is_some())
+        .await
+        .ok()?
+        .clone()?;
+
+    JOBS.lock().unwrap().remove(id);
+    Some(results.as_ref().clone())
+}
+
+// 按工作目录串行运行的目录锁，仅保存弱引用，目录上无任务时自动失效
+// 跨作业共享，多个作业同时处理同一目录时同样逐个运行
+static DIR_LOCKS: LazyLock<Mutex<HashMap<PathBuf, Weak<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 获取工作目录的独占锁，锁在返回的守卫释放时解除
+// 同一目录按请求顺序逐个获得，不同目录互不影响
+pub(crate) async fn lock_dir(dir: &Path) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = DIR_LOCKS.lock().unwrap();
+        locks.retain(|_, lock| lock.strong_count() > 0);
+        match locks.get(dir).and_then(Weak::upgrade) {
+            Some(lock) => lock,
+            None => {
+                let lock = Arc::new(tokio::sync::Mutex::new(()));
+                locks.insert(dir.to_path_buf(), Arc::downgrade(&lock));
+                lock
+            }
+        }
+    };
+    lock.lock_owned().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn runs_in_the_same_directory_take_turns() {
+        let base = std::env::temp_dir().join(format!("cmtools-dirlock-{}", std::process::id()));
+        let (plate1, plate2) = (base.join("plate1"), base.join("plate2"));
+        std::fs::create_dir_all(&plate1).unwrap();
+        std::fs::create_dir_all(&plate2).unwrap();
+        let short = Duration::from_millis(100);
+
+        let first = lock_dir(&plate1).await;
+        // 同一目录等待前一次运行结束，其他目录不受影响
+        assert!(
+            tokio::time::timeout(short, lock_dir(&plate1))
+                .await
+                .is_err()
+        );
+        let other = tokio::time::timeout(short, lock_dir(&plate2))
+            .await
+            .unwrap();
+
+        let waiting = tokio::spawn({
+            let plate1 = plate1.clone();
+            async move {
+                let _second = lock_dir(&plate1).await;
+            }
+        });
+        tokio::time::sleep(short).await;
+        assert!(!waiting.is_finished());
+        drop(first);
+        tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 目录上没有任务后，下次加锁时移除失效的锁
+        drop(other);
+        drop(lock_dir(&base).await);
+        let locks = DIR_LOCKS.lock().unwrap();
+        assert!(!locks.contains_key(&plate1));
+        assert!(!locks.contains_key(&plate2));
+        drop(locks);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -1,34 +1,218 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
+mod api;
+mod cache;
+mod cli;
+mod inputs;
+mod jobs;
+mod manifest;
+mod outputs;
+mod paths;
+mod pipelines;
+mod queue;
+mod registry;
+mod runner;
+mod settings;
+mod staging;
+mod watch;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
 use std::sync::Arc;
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 use std::time::Duration;
-use tauri::Manager;
-use tokio::sync::Semaphore;
-use tokio::task;
-use tokio::time::timeout;
+use tauri::{Emitter, Manager};
+use tokio::sync::{OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+use jobs::{CancelToken, EventSink, FileStatus, JobEvent};
+use outputs::{CollisionPolicy, OutputOptions};
+use registry::BuildSource;
+use runner::{ExecutionRecord, RetryPolicy, RunOutcome};
+use settings::RunLimits;
+
+pub use cli::{run_cli, run_console};
 
 // 自定义错误类型
-#[derive(Debug)]
+// 序列化为 {"kind": "...", "details": {...}}，随处理结果返回前端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "details", rename_all = "snake_case")]
 enum ProcessError {
-    UnknownTool { tool: String },
-    Io(std::io::Error),
-    CommandFailed { message: String },
-    FileProcessing { file: String, message: String },
+    UnknownTool {
+        tool: String,
+    },
+    UnknownVersion {
+        tool: String,
+        version: String,
+    },
+    UnknownJob {
+        job: String,
+    },
+    ToolUnavailable {
+        tool: String,
+    },
+    ChecksumMismatch {
+        tool: String,
+        path: String,
+    },
+    InvalidOption {
+        tool: String,
+        option: String,
+        message: String,
+    },
+    #[serde(with = "io_error_text")]
+    Io(Arc<std::io::Error>),
+    // 工具可执行文件不存在（可能被防病毒软件删除）
+    ExecutableNotFound {
+        path: String,
+    },
+    // 无权限执行工具
+    PermissionDenied {
+        path: String,
+    },
+    CommandFailed {
+        message: String,
+    },
+    // 工具以非零退出码结束
+    NonzeroExit {
+        exit_code: i32,
+        elapsed_ms: u64,
+        stderr: String,
+    },
+    // 工具被信号终止（Unix，如崩溃或被外部终止）
+    Signaled {
+        signal: i32,
+        elapsed_ms: u64,
+        stderr: String,
+    },
+    // 工具超时被终止，附带实际耗时及终止前捕获的标准错误输出
+    Timeout {
+        seconds: u64,
+        elapsed_ms: u64,
+        stderr: String,
+    },
+    FileNotFound {
+        file: String,
+    },
+    // 输出目录无法创建或写入
+    OutputDirUnavailable {
+        path: String,
+        message: String,
+    },
+    // 输入文件筛选规则无效
+    InvalidPattern {
+        pattern: String,
+        message: String,
+    },
+    // 展开目录得到的文件数超出上限
+    TooManyInputFiles {
+        limit: usize,
+    },
+    // 监视文件夹不存在或无法访问（如网络共享断开）
+    WatchFolderUnavailable {
+        path: String,
+    },
+    // 流水线定义无效（名称为空、没有步骤等）
+    InvalidPipeline {
+        name: String,
+        message: String,
+    },
+    // 未找到指定名称的流水线
+    UnknownPipeline {
+        name: String,
+    },
+    // 重试策略中的 retry_on 包含不可重试或不存在的错误类型
+    InvalidRetryKind {
+        kind: String,
+    },
+    FileProcessing {
+        file: String,
+        message: String,
+    },
 }
 
 impl std::fmt::Display for ProcessError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ProcessError::UnknownTool { tool } => write!(f, "Unknown tool name: {}", tool),
+            ProcessError::UnknownVersion { tool, version } => {
+                write!(f, "Unknown version {} for {}", version, tool)
+            }
+            ProcessError::UnknownJob { job } => write!(f, "Unknown job: {}", job),
+            ProcessError::ToolUnavailable { tool } => {
+                write!(f, "Tool executable not available: {}", tool)
+            }
+            ProcessError::ChecksumMismatch { tool, path } => {
+                write!(f, "Checksum mismatch for {}: {}", tool, path)
+            }
+            ProcessError::InvalidOption {
+                tool,
+                option,
+                message,
+            } => write!(f, "Invalid option {} for {}: {}", option, tool, message),
             ProcessError::Io(err) => write!(f, "IO error: {}", err),
+            ProcessError::ExecutableNotFound { path } => {
+                write!(f, "Tool executable not found: {}", path)
+            }
+            ProcessError::PermissionDenied { path } => {
+                write!(f, "Permission denied executing: {}", path)
+            }
             ProcessError::CommandFailed { message } => {
                 write!(f, "Command execution failed: {}", message)
             }
+            ProcessError::NonzeroExit {
+                exit_code,
+                elapsed_ms,
+                stderr,
+            } => write!(
+                f,
+                "Tool exited with code {} after {} ms{}",
+                exit_code,
+                elapsed_ms,
+                stderr_suffix(stderr)
+            ),
+            ProcessError::Signaled {
+                signal,
+                elapsed_ms,
+                stderr,
+            } => write!(
+                f,
+                "Tool terminated by signal {} after {} ms{}",
+                signal,
+                elapsed_ms,
+                stderr_suffix(stderr)
+            ),
+            ProcessError::Timeout {
+                seconds,
+                elapsed_ms,
+                stderr,
+            } => write!(
+                f,
+                "Process killed after {} seconds timeout ({} ms){}",
+                seconds,
+                elapsed_ms,
+                stderr_suffix(stderr)
+            ),
+            ProcessError::FileNotFound { file } => write!(f, "File not found: {}", file),
+            ProcessError::OutputDirUnavailable { path, message } => {
+                write!(f, "Output directory unavailable: {} - {}", path, message)
+            }
+            ProcessError::InvalidPattern { pattern, message } => {
+                write!(f, "Invalid file pattern {}: {}", pattern, message)
+            }
+            ProcessError::TooManyInputFiles { limit } => {
+                write!(f, "More than {} input files", limit)
+            }
+            ProcessError::WatchFolderUnavailable { path } => {
+                write!(f, "Watch folder unavailable: {}", path)
+            }
+            ProcessError::InvalidPipeline { name, message } => {
+                write!(f, "Invalid pipeline {}: {}", name, message)
+            }
+            ProcessError::UnknownPipeline { name } => write!(f, "Unknown pipeline: {}", name),
+            ProcessError::InvalidRetryKind { kind } => {
+                write!(f, "Invalid retry_on error kind: {}", kind)
+            }
             ProcessError::FileProcessing { file, message } => {
                 write!(f, "File processing error: {} - {}", file, message)
             }
@@ -40,161 +224,383 @@ impl std::error::Error for ProcessError {}
 
 impl From<std::io::Error> for ProcessError {
     fn from(err: std::io::Error) -> Self {
-        ProcessError::Io(err)
+        ProcessError::Io(Arc::new(err))
     }
 }
 
-// 动态获取 CPU 线程数作为并发上限
-// 自动适配不同硬件，充分利用多核性能同时避免过度竞争
-static CONCURRENCY_LIMIT: LazyLock<Arc<Semaphore>> = LazyLock::new(|| {
-    let cpu_threads = std::thread::available_parallelism()
-        .map(|p| p.get())
-        .unwrap_or(4); // 回退默认值
-    Arc::new(Semaphore::new(cpu_threads))
-});
-
-// 进程执行超时时间（秒）
-const PROCESS_TIMEOUT_SECS: u64 = 120;
+impl ProcessError {
+    // 错误类型，与序列化后的 kind 一致，用于匹配重试策略
+    fn kind(&self) -> &'static str {
+        match self {
+            ProcessError::UnknownTool { .. } => "unknown_tool",
+            ProcessError::UnknownVersion { .. } => "unknown_version",
+            ProcessError::UnknownJob { .. } => "unknown_job",
+            ProcessError::ToolUnavailable { .. } => "tool_unavailable",
+            ProcessError::ChecksumMismatch { .. } => "checksum_mismatch",
+            ProcessError::InvalidOption { .. } => "invalid_option",
+            ProcessError::Io(_) => "io",
+            ProcessError::ExecutableNotFound { .. } => "executable_not_found",
+            ProcessError::PermissionDenied { .. } => "permission_denied",
+            ProcessError::CommandFailed { .. } => "command_failed",
+            ProcessError::NonzeroExit { .. } => "nonzero_exit",
+            ProcessError::Signaled { .. } => "signaled",
+            ProcessError::Timeout { .. } => "timeout",
+            ProcessError::FileNotFound { .. } => "file_not_found",
+            ProcessError::OutputDirUnavailable { .. } => "output_dir_unavailable",
+            ProcessError::InvalidPattern { .. } => "invalid_pattern",
+            ProcessError::TooManyInputFiles { .. } => "too_many_input_files",
+            ProcessError::WatchFolderUnavailable { .. } => "watch_folder_unavailable",
+            ProcessError::InvalidPipeline { .. } => "invalid_pipeline",
+            ProcessError::UnknownPipeline { .. } => "unknown_pipeline",
+            ProcessError::InvalidRetryKind { .. } => "invalid_retry_kind",
+            ProcessError::FileProcessing { .. } => "file_processing",
+        }
+    }
 
-// 检查是否需要重新写入临时可执行文件
-// 仅在文件不存在或大小不匹配时才写入，避免不必要的 I/O 开销
-fn should_rewrite_exe(exe_path: &Path, exe_data: &[u8]) -> bool {
-    match fs::metadata(exe_path) {
-        Ok(meta) => meta.len() != exe_data.len() as u64,
-        Err(_) => true,
+    // 启动工具进程失败时按错误类型归类
+    fn spawn_failed(err: std::io::Error, exe_path: &Path) -> Self {
+        let path = exe_path.display().to_string();
+        match err.kind() {
+            std::io::ErrorKind::NotFound => ProcessError::ExecutableNotFound { path },
+            std::io::ErrorKind::PermissionDenied => ProcessError::PermissionDenied { path },
+            _ => ProcessError::CommandFailed {
+                message: err.to_string(),
+            },
+        }
     }
-}
 
-// 工具枚举，提供类型安全的工具选择
-#[derive(Debug, Clone, Copy)]
-enum Tool {
-    AneuFiler,
-    Aneu23,
-    SMNFilerV1,
-    SMNFilerV2,
-    SHCarrier,
-    UpdfilerV1,
-    UpdfilerV2,
-    StrMatcher,
-}
-
-impl Tool {
-    // 从字符串解析工具类型
-    fn from_str(s: &str) -> Result<Self, ProcessError> {
-        match s {
-            "AneuFiler" => Ok(Tool::AneuFiler),
-            "Aneu23" => Ok(Tool::Aneu23),
-            "SMNFiler_v1" => Ok(Tool::SMNFilerV1),
-            "SMNFiler_v2" => Ok(Tool::SMNFilerV2),
-            "SHCarrier" => Ok(Tool::SHCarrier),
-            "UPDFiler_v1" => Ok(Tool::UpdfilerV1),
-            "UPDFiler_v2" => Ok(Tool::UpdfilerV2),
-            "STR-Matcher" => Ok(Tool::StrMatcher),
-            _ => Err(ProcessError::UnknownTool {
-                tool: s.to_string(),
-            }),
-        }
-    }
-
-    // 获取可执行文件名（根据平台返回不同文件名）
-    fn exe_name(&self) -> String {
-        let base_name = match self {
-            Tool::AneuFiler => "AneuFiler",
-            Tool::Aneu23 => "Aneu23",
-            Tool::SMNFilerV1 => "SMNFiler_v1",
-            Tool::SMNFilerV2 => "SMNFiler_v2",
-            Tool::SHCarrier => "SHCarrier",
-            Tool::UpdfilerV1 => "UPDFiler_v1",
-            Tool::UpdfilerV2 => "UPDFiler_v2",
-            Tool::StrMatcher => "STR-Matcher",
-        };
+    // 工具运行结束但未成功：Unix 上被信号终止时为 Signaled，否则为 NonzeroExit
+    fn exited(status: ExitStatus, record: &ExecutionRecord) -> Self {
+        let elapsed_ms = record.duration_ms;
+        let stderr = captured_stderr(record);
 
-        #[cfg(target_os = "windows")]
+        #[cfg(unix)]
         {
-            format!("{}.exe", base_name)
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = status.signal() {
+                return ProcessError::Signaled {
+                    signal,
+                    elapsed_ms,
+                    stderr,
+                };
+            }
         }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            base_name.to_string()
+        ProcessError::NonzeroExit {
+            exit_code: status.code().unwrap_or(-1),
+            elapsed_ms,
+            stderr,
         }
     }
 
-    // 获取嵌入的可执行文件数据（根据平台选择不同文件）
-    fn exe_data(&self) -> &'static [u8] {
-        #[cfg(target_os = "windows")]
-        {
-            match self {
-                Tool::AneuFiler => include_bytes!("../../src/assets/AneuFiler.exe"),
-                Tool::Aneu23 => include_bytes!("../../src/assets/Aneu23.exe"),
-                Tool::SMNFilerV1 => include_bytes!("../../src/assets/SMNFiler_v1.exe"),
-                Tool::SMNFilerV2 => include_bytes!("../../src/assets/SMNFiler_v2.exe"),
-                Tool::SHCarrier => include_bytes!("../../src/assets/SHCarrier.exe"),
-                Tool::UpdfilerV1 => include_bytes!("../../src/assets/UPDFiler_v1.exe"),
-                Tool::UpdfilerV2 => include_bytes!("../../src/assets/UPDFiler_v2.exe"),
-                Tool::StrMatcher => include_bytes!("../../src/assets/STR-Matcher.exe"),
-            }
+    // 工具超时被终止：耗时与标准错误输出取自执行记录
+    fn timed_out(seconds: u64, record: &ExecutionRecord) -> Self {
+        ProcessError::Timeout {
+            seconds,
+            elapsed_ms: record.duration_ms,
+            stderr: captured_stderr(record),
         }
+    }
+}
 
-        #[cfg(target_os = "macos")]
-        {
-            match self {
-                Tool::AneuFiler => include_bytes!("../../src/assets/AneuFiler"),
-                Tool::Aneu23 => include_bytes!("../../src/assets/Aneu23"),
-                Tool::SMNFilerV1 => include_bytes!("../../src/assets/SMNFiler_v1"),
-                Tool::SMNFilerV2 => include_bytes!("../../src/assets/SMNFiler_v2"),
-                Tool::SHCarrier => include_bytes!("../../src/assets/SHCarrier"),
-                Tool::UpdfilerV1 => include_bytes!("../../src/assets/UPDFiler_v1"),
-                Tool::UpdfilerV2 => include_bytes!("../../src/assets/UPDFiler_v2"),
-                Tool::StrMatcher => include_bytes!("../../src/assets/STR-Matcher"),
-            }
+// 执行记录中捕获的标准错误输出（内存中最多保留 64 KiB，完整输出见日志文件）
+fn captured_stderr(record: &ExecutionRecord) -> String {
+    record.stderr.text.trim().to_string()
+}
+
+// 错误消息末尾附加的标准错误输出
+fn stderr_suffix(stderr: &str) -> String {
+    if stderr.is_empty() {
+        String::new()
+    } else {
+        format!(" - {}", stderr)
+    }
+}
+
+// IO 错误按文本序列化，反序列化时还原为 ErrorKind::Other
+mod io_error_text {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        err: &Arc<std::io::Error>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(err)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arc<std::io::Error>, D::Error> {
+        String::deserialize(deserializer).map(|message| Arc::new(std::io::Error::other(message)))
+    }
+}
+
+// 信号量及其当前的许可总数，上限变化时据此调整
+type SizedSemaphore = (usize, Arc<Semaphore>);
+
+// 全局并发许可及其大小，跨作业限制同时运行的工具进程总数
+// 设置中的全局上限变化时调整许可数，运行中与新启动的作业共用同一信号量
+static CONCURRENCY_LIMIT: Mutex<Option<SizedSemaphore>> = Mutex::new(None);
+
+// 按工具名称共享的并发许可及其大小，跨作业限制同一工具同时运行的进程数
+// 按工具设置或工具定义中的上限变化时调整许可数，运行中与新启动的作业共用同一信号量
+static TOOL_CONCURRENCY_LIMITS: LazyLock<Mutex<HashMap<String, SizedSemaphore>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 将信号量的许可总数调整为 limit
+// 增大时补充许可；缩小时收回空闲的许可，不足的部分在运行中的进程归还许可后收回，
+// 收回先于此后请求许可的运行，替换信号量则会让新旧信号量上的进程同时运行而超出上限
+fn resize_semaphore(sized: &mut SizedSemaphore, limit: usize) {
+    let (size, semaphore) = sized;
+    if limit > *size {
+        semaphore.add_permits(limit - *size);
+    } else if limit < *size {
+        let excess = *size - limit;
+        let remaining = excess - semaphore.forget_permits(excess);
+        if remaining > 0 {
+            let semaphore = semaphore.clone();
+            tokio::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many_owned(remaining as u32).await {
+                    permits.forget();
+                }
+            });
         }
+    }
+    *size = limit;
+}
 
-        #[cfg(target_os = "linux")]
-        {
-            match self {
-                Tool::AneuFiler => include_bytes!("../../src/assets/AneuFiler"),
-                Tool::Aneu23 => include_bytes!("../../src/assets/Aneu23"),
-                Tool::SMNFilerV1 => include_bytes!("../../src/assets/SMNFiler_v1"),
-                Tool::SMNFilerV2 => include_bytes!("../../src/assets/SMNFiler_v2"),
-                Tool::SHCarrier => include_bytes!("../../src/assets/SHCarrier"),
-                Tool::UpdfilerV1 => include_bytes!("../../src/assets/UPDFiler_v1"),
-                Tool::UpdfilerV2 => include_bytes!("../../src/assets/UPDFiler_v2"),
-                Tool::StrMatcher => include_bytes!("../../src/assets/STR-Matcher"),
+// 获取工具的并发许可
+fn tool_concurrency_limit(tool_name: &str, limit: usize) -> Arc<Semaphore> {
+    let mut limits = TOOL_CONCURRENCY_LIMITS.lock().unwrap();
+    let sized = limits
+        .entry(tool_name.to_string())
+        .or_insert_with(|| (limit, Arc::new(Semaphore::new(limit))));
+    resize_semaphore(sized, limit);
+    sized.1.clone()
+}
+
+// 获取全局并发许可
+// 未设置全局上限时动态获取 CPU 线程数，自动适配不同硬件，充分利用多核性能同时避免过度竞争
+fn concurrency_limit() -> Arc<Semaphore> {
+    let limit = settings::current()
+        .limits
+        .max_parallel
+        .filter(|n| *n > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|p| p.get())
+                .unwrap_or(4) // 回退默认值
+        });
+
+    let mut current = CONCURRENCY_LIMIT.lock().unwrap();
+    let sized = current.get_or_insert_with(|| (limit, Arc::new(Semaphore::new(limit))));
+    resize_semaphore(sized, limit);
+    sized.1.clone()
+}
+
+// 检查是否需要重新写入解压的可执行文件
+// 文件不存在或 SHA-256 与构建时记录的摘要不一致时重新写入，防止运行被损坏或篡改的文件
+fn should_rewrite_exe(exe_path: &Path, expected_sha256: &str) -> bool {
+    match registry::sha256_file(exe_path) {
+        Ok(actual) => actual != expected_sha256,
+        Err(_) => true,
+    }
+}
+
+// 准备工具构建的可执行文件路径，运行前校验 SHA-256
+// 内置工具在加锁后原子写入按摘要存放的持久缓存，校验失败时重新写入一次，仍失败则拒绝运行
+// 外部工具直接使用工具目录中的文件，每次运行前重新校验，防止加载后被替换
+fn prepare_executable(build: &registry::ToolBuild) -> Result<PathBuf, ProcessError> {
+    let checksum_mismatch = |path: &Path| ProcessError::ChecksumMismatch {
+        tool: build.def.name.clone(),
+        path: path.display().to_string(),
+    };
+
+    match &build.source {
+        BuildSource::External { path, sha256 } => {
+            if registry::sha256_file(path)? != *sha256 {
+                return Err(checksum_mismatch(path));
             }
+            Ok(path.clone())
+        }
+        BuildSource::Embedded { data, sha256 } => {
+            // 加锁，避免并发调用及其他实例同时写入或回收
+            let dir = cache::cache_dir()?;
+            let _lock = cache::lock(&dir)?;
+
+            // 按摘要定位缓存条目，已缓存且校验通过时直接复用
+            let exe_path = cache::entry_dir(&dir, sha256)?.join(build.def.exe_name());
+            if should_rewrite_exe(&exe_path, sha256) {
+                cache::write_executable_atomic(&exe_path, data)?;
+                if should_rewrite_exe(&exe_path, sha256) {
+                    return Err(checksum_mismatch(&exe_path));
+                }
+            }
+
+            Ok(exe_path)
         }
     }
+}
+
+// 文件处理结果代码，取值稳定，供前端本地化显示及统计，不随翻译变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ResultCode {
+    Success,
+    FileNotFound,
+    // 工具以非零状态退出
+    NonzeroExit,
+    // 无法启动工具进程
+    SpawnFailed,
+    // 工具被信号终止
+    Signaled,
+    Timeout,
+    Cancelled,
+    NotStarted,
+    // 后台任务异常退出
+    TaskFailed,
+    // 无法准备暂存目录或将结果文件移入输出目录
+    OutputFailed,
+}
 
-    // 检查工具是否支持标准品样本名称
-    fn supports_std_sample(&self) -> bool {
-        matches!(
-            self,
-            Tool::Aneu23 | Tool::SMNFilerV1 | Tool::SMNFilerV2 | Tool::SHCarrier
-        )
-        // UPDFiler_v1 和 UPDFiler_v2 不需要标准品样本名称配置
+impl ResultCode {
+    // 对应的文件执行状态
+    fn status(self) -> FileStatus {
+        match self {
+            ResultCode::Cancelled => FileStatus::Cancelled,
+            ResultCode::NotStarted => FileStatus::NotStarted,
+            _ => FileStatus::Completed,
+        }
     }
 
-    // 检查工具是否支持 Windows 优化
-    fn supports_windows_optimization(&self) -> bool {
-        matches!(
-            self,
-            Tool::SMNFilerV1
-                | Tool::SMNFilerV2
-                | Tool::SHCarrier
-                | Tool::UpdfilerV1
-                | Tool::UpdfilerV2
-                | Tool::StrMatcher
-        )
-        // UPDFiler_v1 和 UPDFiler_v2 都支持 Windows 优化配置
+    // 对应的翻译键
+    fn message_key(self) -> &'static str {
+        match self {
+            ResultCode::Success => "process_success",
+            ResultCode::FileNotFound => "file_not_found",
+            ResultCode::NonzeroExit => "process_failed",
+            ResultCode::SpawnFailed => "execute_failed",
+            ResultCode::Signaled => "process_signaled",
+            ResultCode::Timeout => "process_killed",
+            ResultCode::Cancelled => "process_cancelled",
+            ResultCode::NotStarted => "file_not_started",
+            ResultCode::TaskFailed => "task_execution_failed",
+            ResultCode::OutputFailed => "output_failed",
+        }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ProcessResult {
     success: bool,
+    // 文件在作业中的执行状态：已完成、已取消或未开始
+    status: FileStatus,
+    code: ResultCode,
+    // 按请求语言生成的消息，界面应根据 code、file_name 与 params 自行本地化
     message: String,
     error: Option<String>,
+    // 结构化的错误信息（ProcessError），供界面分类与排查
+    failure: Option<ProcessError>,
     file_path: Option<String>,
+    // 输入文件名（不含目录）
+    file_name: Option<String>,
+    // 消息参数，如超时秒数（seconds）、错误详情（detail）
+    params: BTreeMap<String, String>,
+    // 实际运行的工具构建版本及其 SHA-256
+    tool_version: Option<String>,
+    tool_sha256: Option<String>,
+    // 运行的工具名称及其在流水线中的步骤序号（从 0 开始），单独运行的作业无步骤序号
+    tool_name: Option<String>,
+    step: Option<usize>,
+    // 执行记录：命令行、退出状态、耗时及捕获的输出；未启动工具时为空
+    execution: Option<ExecutionRecord>,
+    // 本次运行在工作目录中新建或修改的文件
+    output_files: Vec<String>,
+    // 按重试策略重试时此前各次失败尝试的结果，本结果即最后一次尝试
+    previous_attempts: Vec<AttemptRecord>,
+}
+
+// 单次失败尝试的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AttemptRecord {
+    // 尝试序号，从 1 开始
+    attempt: u32,
+    code: ResultCode,
+    error: Option<String>,
+    failure: Option<ProcessError>,
+    execution: Option<ExecutionRecord>,
+}
+
+impl AttemptRecord {
+    fn new(attempt: u32, result: ProcessResult) -> Self {
+        AttemptRecord {
+            attempt,
+            code: result.code,
+            error: result.error,
+            failure: result.failure,
+            execution: result.execution,
+        }
+    }
+}
+
+impl ProcessResult {
+    fn new(file_path: &str, code: ResultCode, language: &str) -> Self {
+        let file_name = file_name(file_path);
+        ProcessResult {
+            success: code == ResultCode::Success,
+            status: code.status(),
+            code,
+            message: get_message(code.message_key(), language, Some(&file_name)),
+            error: None,
+            failure: None,
+            file_path: Some(file_path.to_string()),
+            file_name: Some(file_name),
+            params: BTreeMap::new(),
+            tool_version: None,
+            tool_sha256: None,
+            tool_name: None,
+            step: None,
+            execution: None,
+            output_files: Vec::new(),
+            previous_attempts: Vec::new(),
+        }
+    }
+
+    // 附加错误：本地化的错误信息及结构化错误
+    fn with_error(mut self, error: ProcessError, language: &str) -> Self {
+        self.error = Some(process_error_to_localized_string(&error, language));
+        self.failure = Some(error);
+        self
+    }
+
+    // 附加执行记录
+    fn with_execution(mut self, record: ExecutionRecord) -> Self {
+        self.execution = Some(record);
+        self
+    }
+
+    // 附加工具生成的输出文件
+    fn with_output_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.output_files = files
+            .into_iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        self
+    }
+
+    // 附加消息参数
+    fn with_param(mut self, key: &str, value: impl ToString) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    // 记录实际运行的工具构建及流水线步骤
+    fn record_build(&mut self, job: &PreparedJob) {
+        self.tool_name = Some(job.build.def.name.clone());
+        self.tool_version = Some(job.build.version.clone());
+        self.tool_sha256 = Some(job.build.sha256().to_string());
+        self.step = job.step;
+    }
 }
 
 // 静态翻译映射表
@@ -208,15 +614,40 @@ static TRANSLATIONS: LazyLock<HashMap<(&str, &str), &str>> = LazyLock::new(|| {
         (("execute_failed", "zh"), "执行程序失败"),
         (("unknown_tool", "zh"), "未知的工具名称"),
         (("unable_open_directory", "zh"), "无法打开目录"),
-        (("unable_create_temp_file", "zh"), "无法创建临时可执行文件"),
-        (("unable_write_file_data", "zh"), "无法写入可执行文件数据"),
-        (("unable_get_permissions", "zh"), "无法获取文件权限"),
-        (("unable_set_permissions", "zh"), "无法设置可执行权限"),
+        (("unable_open_file", "zh"), "无法打开文件"),
         (("task_execution_failed", "zh"), "任务执行失败"),
+        (("process_killed", "zh"), "处理超时，已终止工具进程"),
+        (("process_cancelled", "zh"), "处理已取消，已终止工具进程"),
+        (("process_signaled", "zh"), "工具进程异常终止"),
+        (("file_not_started", "zh"), "作业已取消，未处理文件"),
+        (("unknown_job_error", "zh"), "未知的作业"),
+        (("timeout_error", "zh"), "执行超时，进程已被终止"),
         (("unknown_tool_error", "zh"), "未知的工具名称"),
+        (("unknown_version_error", "zh"), "未知的工具版本"),
+        (("tool_unavailable_error", "zh"), "工具可执行文件不可用"),
+        (("invalid_option_error", "zh"), "无效的工具选项"),
+        (("checksum_mismatch_error", "zh"), "工具可执行文件校验失败"),
         (("io_error", "zh"), "IO 错误"),
+        (("executable_not_found_error", "zh"), "工具可执行文件不存在"),
+        (("permission_denied_error", "zh"), "无权限执行工具"),
+        (("nonzero_exit_error", "zh"), "工具异常退出，退出码"),
+        (("signaled_error", "zh"), "工具被信号终止，信号"),
         (("command_failed_error", "zh"), "命令执行失败"),
         (("file_processing_error", "zh"), "文件处理错误"),
+        (("output_dir_unavailable_error", "zh"), "输出目录不可用"),
+        (("output_failed", "zh"), "无法将结果文件写入输出目录"),
+        (("invalid_pattern_error", "zh"), "无效的文件筛选规则"),
+        (("too_many_input_files_error", "zh"), "输入文件过多，上限为"),
+        (
+            ("watch_folder_unavailable_error", "zh"),
+            "监视文件夹不存在或无法访问",
+        ),
+        (("invalid_pipeline_error", "zh"), "无效的流水线"),
+        (("unknown_pipeline_error", "zh"), "未知的流水线"),
+        (
+            ("invalid_retry_kind_error", "zh"),
+            "重试策略中无效的错误类型",
+        ),
         // 英文翻译
         (("file_not_found", "en"), "File not found"),
         (("file_not_found_error", "en"), "File not found"),
@@ -225,27 +656,76 @@ static TRANSLATIONS: LazyLock<HashMap<(&str, &str), &str>> = LazyLock::new(|| {
         (("execute_failed", "en"), "Failed to execute program"),
         (("unknown_tool", "en"), "Unknown tool name"),
         (("unable_open_directory", "en"), "Unable to open directory"),
+        (("unable_open_file", "en"), "Unable to open file"),
+        (("task_execution_failed", "en"), "Task execution failed"),
         (
-            ("unable_create_temp_file", "en"),
-            "Unable to create temporary executable file",
+            ("process_killed", "en"),
+            "Processing timed out, tool process killed",
         ),
         (
-            ("unable_write_file_data", "en"),
-            "Unable to write executable file data",
+            ("process_cancelled", "en"),
+            "Processing cancelled, tool process killed",
         ),
         (
-            ("unable_get_permissions", "en"),
-            "Unable to get file permissions",
+            ("process_signaled", "en"),
+            "Tool process terminated abnormally",
         ),
         (
-            ("unable_set_permissions", "en"),
-            "Unable to set executable permissions",
+            ("file_not_started", "en"),
+            "Job cancelled, file not processed",
+        ),
+        (("unknown_job_error", "en"), "Unknown job"),
+        (
+            ("timeout_error", "en"),
+            "Execution timed out, process killed",
         ),
-        (("task_execution_failed", "en"), "Task execution failed"),
         (("unknown_tool_error", "en"), "Unknown tool name"),
+        (("unknown_version_error", "en"), "Unknown tool version"),
+        (
+            ("tool_unavailable_error", "en"),
+            "Tool executable not available",
+        ),
+        (("invalid_option_error", "en"), "Invalid tool option"),
+        (
+            ("checksum_mismatch_error", "en"),
+            "Tool executable checksum mismatch",
+        ),
         (("io_error", "en"), "IO error"),
+        (
+            ("executable_not_found_error", "en"),
+            "Tool executable not found",
+        ),
+        (
+            ("permission_denied_error", "en"),
+            "Permission denied executing tool",
+        ),
+        (("nonzero_exit_error", "en"), "Tool exited with code"),
+        (("signaled_error", "en"), "Tool terminated by signal"),
         (("command_failed_error", "en"), "Command execution failed"),
         (("file_processing_error", "en"), "File processing error"),
+        (
+            ("output_dir_unavailable_error", "en"),
+            "Output directory unavailable",
+        ),
+        (
+            ("output_failed", "en"),
+            "Failed to write result files to output directory",
+        ),
+        (("invalid_pattern_error", "en"), "Invalid file pattern"),
+        (
+            ("too_many_input_files_error", "en"),
+            "Too many input files, limit is",
+        ),
+        (
+            ("watch_folder_unavailable_error", "en"),
+            "Watch folder unavailable",
+        ),
+        (("invalid_pipeline_error", "en"), "Invalid pipeline"),
+        (("unknown_pipeline_error", "en"), "Unknown pipeline"),
+        (
+            ("invalid_retry_kind_error", "en"),
+            "Invalid error kind in retry policy",
+        ),
     ])
 });
 
@@ -277,9 +757,66 @@ fn process_error_to_localized_string(error: &ProcessError, language: &str) -> St
                 tool
             )
         }
+        ProcessError::UnknownVersion { tool, version } => {
+            format!(
+                "{}: {} {}",
+                get_message("unknown_version_error", language, None),
+                tool,
+                version
+            )
+        }
+        ProcessError::UnknownJob { job } => {
+            format!(
+                "{}: {}",
+                get_message("unknown_job_error", language, None),
+                job
+            )
+        }
+        ProcessError::ToolUnavailable { tool } => {
+            format!(
+                "{}: {}",
+                get_message("tool_unavailable_error", language, None),
+                tool
+            )
+        }
+        ProcessError::ChecksumMismatch { tool, path } => {
+            format!(
+                "{}: {} ({})",
+                get_message("checksum_mismatch_error", language, None),
+                tool,
+                path
+            )
+        }
+        ProcessError::InvalidOption {
+            tool,
+            option,
+            message,
+        } => {
+            format!(
+                "{}: {} ({}) - {}",
+                get_message("invalid_option_error", language, None),
+                option,
+                tool,
+                message
+            )
+        }
         ProcessError::Io(err) => {
             format!("{}: {}", get_message("io_error", language, None), err)
         }
+        ProcessError::ExecutableNotFound { path } => {
+            format!(
+                "{}: {}",
+                get_message("executable_not_found_error", language, None),
+                path
+            )
+        }
+        ProcessError::PermissionDenied { path } => {
+            format!(
+                "{}: {}",
+                get_message("permission_denied_error", language, None),
+                path
+            )
+        }
         ProcessError::CommandFailed { message } => {
             format!(
                 "{}: {}",
@@ -287,6 +824,93 @@ fn process_error_to_localized_string(error: &ProcessError, language: &str) -> St
                 message
             )
         }
+        ProcessError::NonzeroExit {
+            exit_code, stderr, ..
+        } => {
+            format!(
+                "{}: {}{}",
+                get_message("nonzero_exit_error", language, None),
+                exit_code,
+                stderr_suffix(stderr)
+            )
+        }
+        ProcessError::Signaled { signal, stderr, .. } => {
+            format!(
+                "{}: {}{}",
+                get_message("signaled_error", language, None),
+                signal,
+                stderr_suffix(stderr)
+            )
+        }
+        ProcessError::Timeout {
+            seconds, stderr, ..
+        } => {
+            format!(
+                "{}: {}s{}",
+                get_message("timeout_error", language, None),
+                seconds,
+                stderr_suffix(stderr)
+            )
+        }
+        ProcessError::FileNotFound { file } => {
+            format!(
+                "{}: {}",
+                get_message("file_not_found_error", language, None),
+                file
+            )
+        }
+        ProcessError::OutputDirUnavailable { path, message } => {
+            format!(
+                "{}: {} - {}",
+                get_message("output_dir_unavailable_error", language, None),
+                path,
+                message
+            )
+        }
+        ProcessError::InvalidPattern { pattern, message } => {
+            format!(
+                "{}: {} - {}",
+                get_message("invalid_pattern_error", language, None),
+                pattern,
+                message
+            )
+        }
+        ProcessError::TooManyInputFiles { limit } => {
+            format!(
+                "{} {}",
+                get_message("too_many_input_files_error", language, None),
+                limit
+            )
+        }
+        ProcessError::WatchFolderUnavailable { path } => {
+            format!(
+                "{}: {}",
+                get_message("watch_folder_unavailable_error", language, None),
+                path
+            )
+        }
+        ProcessError::InvalidPipeline { name, message } => {
+            format!(
+                "{}: {} - {}",
+                get_message("invalid_pipeline_error", language, None),
+                name,
+                message
+            )
+        }
+        ProcessError::UnknownPipeline { name } => {
+            format!(
+                "{}: {}",
+                get_message("unknown_pipeline_error", language, None),
+                name
+            )
+        }
+        ProcessError::InvalidRetryKind { kind } => {
+            format!(
+                "{}: {}",
+                get_message("invalid_retry_kind_error", language, None),
+                kind
+            )
+        }
         ProcessError::FileProcessing { file, message } => {
             format!(
                 "{}: {} - {}",
@@ -372,78 +996,75 @@ async fn open_file_directory(file_path: String, language: Option<String>) -> Res
     Ok(())
 }
 
-// 获取工具版本号的命令
+// 使用系统默认程序打开文件的命令（如工具生成的 Excel 或文本报告）
 #[tauri::command]
-async fn get_tool_version(tool_name: String, language: Option<String>) -> Result<String, String> {
+async fn open_output_file(file_path: String, language: Option<String>) -> Result<(), String> {
     let lang = language.as_deref().unwrap_or("en");
+    let open_error =
+        |e: std::io::Error| format!("{}: {}", get_message("unable_open_file", lang, None), e);
 
-    // 解析工具类型
-    let tool =
-        Tool::from_str(&tool_name).map_err(|e| process_error_to_localized_string(&e, lang))?;
+    #[cfg(target_os = "windows")]
+    let mut cmd = Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut cmd = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut cmd = Command::new("xdg-open");
 
-    // 获取工具的可执行文件信息
-    let exe_name = tool.exe_name();
-    let exe_data = tool.exe_data();
+    cmd.arg(&file_path).spawn().map_err(open_error)?;
+    Ok(())
+}
 
-    // 获取临时目录
-    let temp_dir = std::env::temp_dir();
-    let exe_path = temp_dir.join(format!("cmtools_{}", exe_name));
+// 查询工具版本（--version）的超时时间，工具无响应时不阻塞工具列表
+const VERSION_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
 
-    // 仅在文件不存在或大小不匹配时写入
-    if should_rewrite_exe(&exe_path, exe_data) {
-        // 将嵌入的可执行文件写入临时目录
-        let mut file = fs::File::create(&exe_path).map_err(|e| {
-            format!(
-                "{}: {}",
-                get_message("unable_create_temp_file", lang, None),
-                e
-            )
-        })?;
-        file.write_all(exe_data).map_err(|e| {
-            format!(
-                "{}: {}",
-                get_message("unable_write_file_data", lang, None),
-                e
-            )
-        })?;
+// 获取工具版本号的命令
+#[tauri::command]
+async fn get_tool_version(
+    tool_name: String,
+    version: Option<String>,
+    language: Option<String>,
+) -> Result<String, String> {
+    let lang = language.as_deref().unwrap_or("en");
 
-        // 在Unix系统上设置可执行权限
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&exe_path)
-                .map_err(|e| {
-                    format!(
-                        "{}: {}",
-                        get_message("unable_get_permissions", lang, None),
-                        e
-                    )
-                })?
-                .permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&exe_path, perms).map_err(|e| {
-                format!(
-                    "{}: {}",
-                    get_message("unable_set_permissions", lang, None),
-                    e
-                )
-            })?;
-        }
-    }
+    // 查找工具构建，未指定版本时使用默认构建
+    let build = registry::current()
+        .find(&tool_name, version.as_deref())
+        .map_err(|e| process_error_to_localized_string(&e, lang))?;
 
-    // 执行工具的 --version 命令
-    let mut cmd = Command::new(&exe_path);
-    cmd.arg("--version");
+    // 准备可执行文件（含缓存锁与校验）并执行 --version，超时后终止工具进程并报错
+    let probe = async {
+        // 缓存锁与计算校验和会阻塞，在阻塞线程池中进行
+        let exe_path = tokio::task::spawn_blocking(move || prepare_executable(&build))
+            .await
+            .unwrap_or_else(|e| Err(std::io::Error::other(e).into()))
+            .map_err(|e| process_error_to_localized_string(&e, lang))?;
 
-    // 在 Windows 上隐藏命令行窗口
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
+        let mut cmd = tokio::process::Command::new(&exe_path);
+        cmd.arg("--version").kill_on_drop(true);
+
+        // 在 Windows 上隐藏命令行窗口
+        #[cfg(target_os = "windows")]
         cmd.creation_flags(0x08000000);
-    }
 
-    // 执行命令并获取输出
-    match cmd.output() {
+        Ok::<_, String>(cmd.output().await)
+    };
+    let output = match tokio::time::timeout(VERSION_PROBE_TIMEOUT, probe).await {
+        Ok(output) => output?,
+        Err(_) => {
+            return Err(format!(
+                "{}: {}s",
+                if lang == "zh" {
+                    "获取版本信息超时"
+                } else {
+                    "Timed out getting version info"
+                },
+                VERSION_PROBE_TIMEOUT.as_secs()
+            ));
+        }
+    };
+
+    // 解析命令输出
+    match output {
         Ok(output) => {
             if output.status.success() {
                 // 从 stdout 获取版本信息
@@ -494,313 +1115,1049 @@ async fn get_tool_version(tool_name: String, language: Option<String>) -> Result
     }
 }
 
+// 列出全部工具的显示信息、选项 schema 和版本号，供前端动态渲染
+#[tauri::command]
+async fn list_tools(language: Option<String>) -> Vec<manifest::ToolInfo> {
+    // 并行查询各工具版本，查询失败或超时时版本号留空
+    let registry = registry::current();
+    let probes: Vec<_> = registry
+        .tools()
+        .map(|build| {
+            let build = build.clone();
+            let versions = registry.versions(&build.def.name);
+            let language = language.clone();
+            tokio::spawn(async move {
+                let version = get_tool_version(
+                    build.def.name.clone(),
+                    Some(build.version.clone()),
+                    language,
+                )
+                .await
+                .ok();
+                build.def.info(version, build.version.clone(), versions)
+            })
+        })
+        .collect();
+
+    let mut tools = Vec::new();
+    for probe in probes {
+        if let Ok(info) = probe.await {
+            tools.push(info);
+        }
+    }
+    tools
+}
+
+// 作业运行方式，由调用方随作业一并传入，缺省字段取默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct RunOptions {
+    // 结果文件的输出目录及同名文件处理方式，未指定时结果文件写在输入文件旁
+    output: Option<OutputOptions>,
+    // 沙箱模式：每个输入文件复制到独立的暂存目录中处理，工具不接触原始输入目录
+    sandbox: bool,
+    // 本作业的并发上限与超时，优先于设置及工具定义
+    limits: RunLimits,
+    // 本作业的重试策略，未指定时使用设置中的策略
+    retry: Option<RetryPolicy>,
+}
+
+// 已准备好的作业：工具构建、校验后的选项、可执行文件路径及运行方式
+struct PreparedJob {
+    build: Arc<registry::ToolBuild>,
+    options: manifest::ResolvedOptions,
+    exe_path: PathBuf,
+    run: RunOptions,
+    // 本作业同时运行的文件数上限，None 时仅受工具与全局上限限制
+    max_parallel: Option<usize>,
+    // 该工具在所有作业中同时运行的进程数上限
+    tool_max_parallel: Option<usize>,
+    // 单个文件的处理超时时间（秒）
+    timeout_secs: u64,
+    retry: RetryPolicy,
+    language: String,
+    // 流水线中的步骤序号，单独运行的作业为 None
+    step: Option<usize>,
+    // 文件序号的起始值，流水线各步骤的文件序号依次相接，事件与暂存目录不会重复
+    first_index: usize,
+}
+
+impl PreparedJob {
+    // 需要按目录逐个运行时返回工具的工作目录
+    // 仅当工具写入固定文件名的共享输出且直接在输入文件所在目录中运行时需要；
+    // 沙箱模式或指定输出目录时每个文件使用独立的暂存目录，无需串行
+    fn shared_work_dir(&self, file_path: &str) -> Option<PathBuf> {
+        if !self.build.def.shared_outputs || self.run.sandbox || self.run.output.is_some() {
+            return None;
+        }
+        let dir = Path::new(file_path).parent().unwrap_or(Path::new("."));
+        // 规范化路径，避免同一目录的不同写法被当作不同目录
+        Some(std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf()))
+    }
+}
+
+// 查找指定版本（缺省为默认构建）、校验选项、确定并发上限与超时、准备可执行文件并创建输出目录
+fn prepare_job(
+    tool_name: &str,
+    version: Option<&str>,
+    options: &Map<String, Value>,
+    run: RunOptions,
+    language: &str,
+) -> Result<PreparedJob, ProcessError> {
+    let build = registry::current().find(tool_name, version)?;
+    let options = build.def.resolve_options(options)?;
+    let exe_path = prepare_executable(&build)?;
+
+    // 超时：作业 > 按工具设置 > 工具定义 > 全局设置 > 工具清单中的默认值
+    // 并发上限分三级同时生效：作业上限仅限本作业，工具上限（按工具设置 > 工具定义）由该工具的所有作业共享，
+    // 全局上限限制所有作业的进程总数
+    let settings = settings::current();
+    let tool_limits = settings
+        .tool_limits
+        .get(&build.def.name)
+        .copied()
+        .unwrap_or_default()
+        .or(build.def.limits);
+    let timeout_secs = run
+        .limits
+        .or(tool_limits)
+        .or(settings.limits)
+        .timeout_secs
+        .unwrap_or(manifest::defaults().timeout_secs);
+    let max_parallel = run.limits.max_parallel.filter(|n| *n > 0);
+
+    let retry = run.retry.clone().unwrap_or(settings.retry);
+    retry.validate()?;
+
+    if let Some(output) = &run.output {
+        std::fs::create_dir_all(&output.dir).map_err(|e| ProcessError::OutputDirUnavailable {
+            path: output.dir.display().to_string(),
+            message: e.to_string(),
+        })?;
+    }
+
+    Ok(PreparedJob {
+        build,
+        options,
+        exe_path,
+        retry,
+        run,
+        max_parallel,
+        tool_max_parallel: tool_limits.max_parallel,
+        timeout_secs,
+        language: language.to_string(),
+        step: None,
+        first_index: 0,
+    })
+}
+
 // 内部处理函数，使用 ProcessError
-async fn process_files_internal(
-    _app: tauri::AppHandle,
+// 校验通过后在后台启动作业，返回作业 ID；作业进度以 Tauri 事件发送给界面
+async fn start_job_internal(
+    app: tauri::AppHandle,
     tool_name: String,
+    version: Option<String>,
     file_paths: Vec<String>,
-    use_area_data: bool,
-    std_sample_name: Option<String>,
-    windows_optimization: Option<bool>,
-    verbose_log: Option<bool>,
+    options: Map<String, Value>,
+    run: RunOptions,
     language: Option<String>,
-    tolerance: Option<f64>,
-) -> Result<Vec<ProcessResult>, ProcessError> {
-    let lang = language.as_deref().unwrap_or("en");
-    let mut results = Vec::new();
+) -> Result<String, ProcessError> {
+    let lang = language.unwrap_or_else(|| "en".to_string());
+    // 准备可执行文件（缓存文件锁、校验释放的文件）会阻塞，在阻塞线程池中进行
+    let job = tauri::async_runtime::spawn_blocking(move || {
+        prepare_job(&tool_name, version.as_deref(), &options, run, &lang)
+    })
+    .await
+    .map_err(|e| std::io::Error::other(e.to_string()))??;
+    let job = Arc::new(job);
+    let events: EventSink = Arc::new(move |event: JobEvent| {
+        let _ = app.emit(event.name(), event);
+    });
+    Ok(jobs::spawn(move |job_id, cancel| {
+        run_job(job_id, job, file_paths, cancel, events)
+    }))
+}
+
+// 在后台启动流水线作业，返回作业 ID；进度事件与 start_job 相同
+async fn start_pipeline_internal(
+    app: tauri::AppHandle,
+    name: String,
+    file_paths: Vec<String>,
+    run: RunOptions,
+    language: String,
+) -> Result<String, ProcessError> {
+    // 与 start_job_internal 相同，准备各步骤的可执行文件在阻塞线程池中进行
+    let pipeline =
+        tauri::async_runtime::spawn_blocking(move || prepare_pipeline(&name, run, &language))
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))??;
+    let events: EventSink = Arc::new(move |event: JobEvent| {
+        let _ = app.emit(event.name(), event);
+    });
+    Ok(jobs::spawn(move |job_id, cancel| {
+        run_pipeline(job_id, pipeline, file_paths, cancel, events)
+    }))
+}
 
-    // 解析工具类型
-    let tool = Tool::from_str(&tool_name)?;
+// 通知界面队列已变化，附带全部批次
+fn emit_queue_changed(app: &tauri::AppHandle) {
+    let _ = app.emit("queue-changed", queue::list());
+}
 
-    // 获取工具的可执行文件信息
-    let exe_name = tool.exe_name();
-    let exe_data = tool.exe_data();
+// 队列调度：按队列顺序逐个运行批次，批次内的文件仍按并发上限并行处理
+// 队列为空时等待新批次提交或已结束的批次被继续
+async fn run_queue(app: tauri::AppHandle) {
+    // 保存队列与准备可执行文件（缓存文件锁、校验释放的文件）会阻塞，均在阻塞线程池中进行，
+    // 避免一次等待锁占住运行时的工作线程并拖住整个队列
+    loop {
+        let next = tokio::task::spawn_blocking(queue::next)
+            .await
+            .unwrap_or_default();
+        let Some(batch) = next else {
+            queue::wait_for_work().await;
+            continue;
+        };
+        emit_queue_changed(&app);
+
+        let lang = batch.language.clone();
+        let prepare_batch = batch.clone();
+        let prepared = tokio::task::spawn_blocking(move || {
+            prepare_job(
+                &prepare_batch.tool_name,
+                prepare_batch.version.as_deref(),
+                &prepare_batch.options,
+                prepare_batch.run,
+                &prepare_batch.language,
+            )
+        })
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e).into()));
+        let job = match prepared {
+            Ok(job) => Arc::new(job),
+            Err(e) => {
+                let batch_id = batch.id.clone();
+                let error = process_error_to_localized_string(&e, &lang);
+                let _ = tokio::task::spawn_blocking(move || queue::fail(&batch_id, error)).await;
+                emit_queue_changed(&app);
+                continue;
+            }
+        };
+
+        // 作业事件照常发送给界面，同时将每个文件的结果记录到队列，中断后据此继续
+        // 监视文件夹提交的批次另将结果文件记录到该监视，避免结果文件被再次提交处理
+        let batch_id = batch.id.clone();
+        let watch_id = batch.watch_id.clone();
+        let events_app = app.clone();
+        let events: EventSink = Arc::new(move |event: JobEvent| {
+            if let JobEvent::FileFinished { result, .. } = &event {
+                queue::record_result(&batch_id, result);
+                if let Some(watch_id) = &watch_id {
+                    watch::record_outputs(watch_id, &result.output_files);
+                }
+                emit_queue_changed(&events_app);
+            }
+            let _ = events_app.emit(event.name(), event);
+        });
+
+        // 继续处理已取消或已中断的批次时只运行尚未结束的文件
+        let pending = batch.pending_files();
+        let job_id =
+            jobs::spawn(move |job_id, cancel| run_job(job_id, job, pending, cancel, events));
+        let (batch_id, running_job) = (batch.id.clone(), job_id.clone());
+        let _ = tokio::task::spawn_blocking(move || queue::set_job(&batch_id, &running_job)).await;
+        emit_queue_changed(&app);
+
+        let results = jobs::wait(&job_id).await.unwrap_or_default();
+        let batch_id = batch.id.clone();
+        let _ = tokio::task::spawn_blocking(move || queue::finish(&batch_id, results)).await;
+        emit_queue_changed(&app);
+    }
+}
 
-    // 获取临时目录
-    let temp_dir = std::env::temp_dir();
-    let exe_path = temp_dir.join(format!("cmtools_{}", exe_name));
+// 通知界面监视文件夹状态已变化，附带全部监视文件夹
+fn emit_watches_changed(app: &tauri::AppHandle) {
+    let _ = app.emit("watches-changed", watch::list());
+}
 
-    // 仅在文件不存在或大小不匹配时写入，避免不必要的 I/O 开销
-    if should_rewrite_exe(&exe_path, exe_data) {
-        // 将嵌入的可执行文件写入临时目录
-        let mut file = fs::File::create(&exe_path)?;
-        file.write_all(exe_data)?;
+// 监视文件夹调度：定时扫描，将导出完成的新文件作为批次提交到作业队列
+// 批次与手动提交的批次一样按队列顺序运行，结果记录在队列中，输出按监视设置中的 run 放置
+async fn run_watches(app: tauri::AppHandle) {
+    let mut interval = tokio::time::interval(watch::POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        // 扫描文件夹为阻塞操作，放到阻塞线程池中执行
+        let Ok((ready, changed)) =
+            tokio::task::spawn_blocking(|| watch::poll(queue::is_active)).await
+        else {
+            continue;
+        };
 
-        // 在Unix系统上设置可执行权限
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&exe_path)?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&exe_path, perms)?;
+        for ready in &ready {
+            let config = ready.config.clone();
+            let batch_id = queue::submit(
+                config.tool_name,
+                config.version,
+                ready.files.clone(),
+                config.options,
+                config.run,
+                config.language,
+                Some(ready.watch_id.clone()),
+            );
+            watch::set_last_batch(&ready.watch_id, &batch_id);
+        }
+        if !ready.is_empty() {
+            emit_queue_changed(&app);
+        }
+        if changed {
+            emit_watches_changed(&app);
         }
     }
+}
 
-    // 使用信号量限制并发数，避免线程池被耗尽
+// 执行作业中的全部文件（带并发限制、超时控制与取消），并发出进度事件
+async fn run_job(
+    job_id: String,
+    job: Arc<PreparedJob>,
+    file_paths: Vec<String>,
+    cancel: CancelToken,
+    events: EventSink,
+) -> Vec<ProcessResult> {
+    events(JobEvent::JobStarted {
+        job_id: job_id.clone(),
+        tool: job.build.def.name.clone(),
+        version: job.build.version.clone(),
+        total: file_paths.len(),
+    });
+
+    // 使用信号量限制并发数，避免同时启动过多工具进程
+    // 全局信号量限制所有作业的进程总数，工具信号量限制同一工具在所有作业中的进程数，作业信号量限制本作业的并发数
     // Semaphore::clone() 返回 Arc<Semaphore>，可以安全地在多个任务间共享
-    let semaphore = CONCURRENCY_LIMIT.clone();
-
-    // 并行处理文件（带并发限制和超时控制）
-    let tasks: Vec<_> = file_paths.into_iter().map(|file_path| {
-        let exe_path = exe_path.clone();
-        let _tool_name = tool_name.clone();
-        let std_sample_name = std_sample_name.clone();
-        let lang = lang.to_string();
-        let lang_for_timeout = lang.clone(); // 用于超时错误处理
-        let semaphore = semaphore.clone();
-
-        async move {
-            // 获取信号量许可，限制并发数
-            let _permit = semaphore.acquire().await.unwrap();
-
-            // 使用 spawn_blocking 执行同步的文件处理
-            let task_handle = task::spawn_blocking(move || -> ProcessResult {
-                let file_path_obj = Path::new(&file_path);
-
-                // 检查文件是否存在
-                if !file_path_obj.exists() {
-                    let error = ProcessError::FileProcessing {
-                        file: file_path.clone(),
-                        message: get_message("file_not_found_error", &lang, None),
-                    };
-                    return ProcessResult {
-                        success: false,
-                        message: get_message("file_not_found", &lang, Some(&file_path)),
-                        error: Some(process_error_to_localized_string(&error, &lang)),
-                        file_path: Some(file_path.clone()),
-                    };
-                }
+    let semaphore = concurrency_limit();
+    let tool_semaphore = job
+        .tool_max_parallel
+        .map(|n| tool_concurrency_limit(&job.build.def.name, n));
+    let job_semaphore = job.max_parallel.map(|n| Arc::new(Semaphore::new(n)));
+
+    // 并行处理文件
+    let tasks: Vec<_> = file_paths
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(index, file_path)| {
+            let index = job.first_index + index;
+            let job_id = job_id.clone();
+            let job = job.clone();
+            let slots = RunSlots {
+                global: semaphore.clone(),
+                tool: tool_semaphore.clone(),
+                job: job_semaphore.clone(),
+            };
+            let cancel = cancel.clone();
+            let events = events.clone();
+
+            tokio::spawn(async move {
+                let mut result = process_file_with_retry(
+                    &job, &file_path, &slots, cancel, &job_id, index, &events,
+                )
+                .await;
+
+                // 记录实际运行的构建，便于结果追溯
+                result.record_build(&job);
+                events(JobEvent::FileFinished {
+                    job_id,
+                    index,
+                    result: Box::new(result.clone()),
+                });
+                result
+            })
+        })
+        .collect();
 
-                // 获取文件所在目录
-                let file_dir = file_path_obj.parent().unwrap_or(Path::new("."));
+    // 等待所有任务完成并按输入顺序收集结果
+    let mut results = Vec::new();
+    for (index, (task, file_path)) in tasks.into_iter().zip(&file_paths).enumerate() {
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) => {
+                let mut result =
+                    ProcessResult::new(file_path, ResultCode::TaskFailed, &job.language)
+                        .with_param("detail", &e);
+                result.error = Some(e.to_string());
+                result.record_build(&job);
+                events(JobEvent::FileFinished {
+                    job_id: job_id.clone(),
+                    index: job.first_index + index,
+                    result: Box::new(result.clone()),
+                });
+                result
+            }
+        };
+        results.push(result);
+    }
 
-                // 根据不同工具构建命令行参数
-                let mut cmd = Command::new(&exe_path);
+    events(JobEvent::finished(&job_id, &results));
+    results
+}
 
-                // 在 Windows 上隐藏命令行窗口
-                #[cfg(target_os = "windows")]
-                {
-                    use std::os::windows::process::CommandExt;
-                    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-                }
+// 已准备好的流水线：名称、步骤依次或同时运行（mode）及各步骤的作业
+struct PreparedPipeline {
+    name: String,
+    mode: pipelines::PipelineMode,
+    steps: Vec<PreparedJob>,
+}
 
-                // 添加输入文件参数
-                cmd.arg("-i").arg(&file_path);
+// 按名称查找流水线并准备各步骤，各步骤共用同一运行方式（输出目录、沙箱、并发上限与超时）
+// 任一步骤的工具不可用或选项无效时整个流水线不启动
+fn prepare_pipeline(
+    name: &str,
+    run: RunOptions,
+    language: &str,
+) -> Result<PreparedPipeline, ProcessError> {
+    let pipeline = pipelines::find(name)?;
+    if pipeline.steps.is_empty() {
+        return Err(ProcessError::InvalidPipeline {
+            name: pipeline.name,
+            message: "no steps".to_string(),
+        });
+    }
+    let steps = pipeline
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| {
+            let mut job = prepare_job(
+                &step.tool_name,
+                step.version.as_deref(),
+                &step.options,
+                run.clone(),
+                language,
+            )?;
+            job.step = Some(index);
+            Ok(job)
+        })
+        .collect::<Result<_, ProcessError>>()?;
+    Ok(PreparedPipeline {
+        name: pipeline.name,
+        mode: pipeline.mode,
+        steps,
+    })
+}
 
-                // STR-Matcher 的 Tolerance 参数（仅当值大于 0 时添加）
-                if let Tool::StrMatcher = tool {
-                    if let Some(tol_value) = tolerance {
-                        if tol_value > 0.0 {
-                            cmd.arg("-t").arg(tol_value.to_string());
-                        }
-                    }
-                }
+// 以一个作业运行流水线：每个步骤处理全部文件，结果按步骤顺序排列，每个结果带有步骤序号
+// 顺序模式下上一步骤的全部文件结束后再运行下一步骤；某一步骤失败不影响后续步骤，取消后其余步骤不再启动
+async fn run_pipeline(
+    job_id: String,
+    pipeline: PreparedPipeline,
+    file_paths: Vec<String>,
+    cancel: CancelToken,
+    events: EventSink,
+) -> Vec<ProcessResult> {
+    // 流水线包含多个工具构建，没有单一版本
+    events(JobEvent::JobStarted {
+        job_id: job_id.clone(),
+        tool: pipeline.name.clone(),
+        version: String::new(),
+        total: pipeline.steps.len() * file_paths.len(),
+    });
+
+    // 各步骤的作业开始与结束事件不转发，对外只有整个流水线一个作业
+    let step_events: EventSink = {
+        let events = events.clone();
+        Arc::new(move |event: JobEvent| {
+            if matches!(
+                event,
+                JobEvent::FileStarted { .. } | JobEvent::FileFinished { .. }
+            ) {
+                events(event);
+            }
+        })
+    };
+    let runs = pipeline
+        .steps
+        .into_iter()
+        .enumerate()
+        .map(|(step, mut job)| {
+            job.first_index = step * file_paths.len();
+            run_job(
+                job_id.clone(),
+                Arc::new(job),
+                file_paths.clone(),
+                cancel.clone(),
+                step_events.clone(),
+            )
+        });
 
-                // 为 AneuFiler、Aneu23 和 SHCarrier 添加默认的 -dev 参数
-                if let Tool::AneuFiler | Tool::Aneu23 | Tool::SHCarrier = tool {
-                    cmd.arg("-dev");
-                }
+    let mut results = Vec::new();
+    match pipeline.mode {
+        pipelines::PipelineMode::Sequential => {
+            for run in runs {
+                results.extend(run.await);
+            }
+        }
+        pipelines::PipelineMode::Parallel => {
+            let tasks: Vec<_> = runs.map(tokio::spawn).collect();
+            for task in tasks {
+                results.extend(task.await.unwrap_or_default());
+            }
+        }
+    }
 
-                // 添加峰面积数据参数（除 UPDFiler_v1、UPDFiler_v2 和 SMNFiler_v2 外的工具都支持）
-                if use_area_data && !matches!(tool, Tool::UpdfilerV1 | Tool::UpdfilerV2 | Tool::SMNFilerV2) {
-                    match tool {
-                        Tool::SMNFilerV1 => cmd.arg("-a"),
-                        _ => cmd.arg("-Area"),
-                    };
-                }
+    events(JobEvent::finished(&job_id, &results));
+    results
+}
 
-                // 添加标准品样本名称参数（仅部分工具支持）
-                if tool.supports_std_sample() {
-                    if let Some(ref std_name) = std_sample_name {
-                        if !std_name.trim().is_empty() {
-                            match tool {
-                                Tool::SMNFilerV1 => cmd.arg("-c").arg(std_name.trim()),
-                                Tool::SMNFilerV2 => cmd.arg("-STD").arg(std_name.trim()),
-                                _ => cmd.arg("-STD").arg(std_name.trim()),
-                            };
-                        }
-                    }
-                }
+// 获取路径中的文件名部分，用于结果消息
+fn file_name(file_path: &str) -> String {
+    Path::new(file_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
 
-                // 添加 Windows 优化参数（SMNFiler_v1、SHCarrier、UPDFiler_v1 和 UPDFiler_v2 支持）
-                if tool.supports_windows_optimization() {
-                    if windows_optimization.unwrap_or(false) {
-                        match tool {
-                            Tool::SMNFilerV1 => cmd.arg("-e").arg("GBK"),
-                            Tool::SMNFilerV2 => cmd.arg("-GBK"),
-                            Tool::UpdfilerV1 => cmd.arg("-e").arg("GBK"),
-                            _ => cmd.arg("-GBK"),
-                        };
-                    }
-                }
+// 运行工具进程所需的全局、工具与作业信号量
+struct RunSlots {
+    global: Arc<Semaphore>,
+    tool: Option<Arc<Semaphore>>,
+    job: Option<Arc<Semaphore>>,
+}
 
-                // 为 SMNFiler_v1 添加特殊参数
-                if let Tool::SMNFilerV1 = tool {
-                    // 添加输出路径参数（使用输入文件所在目录）
-                    if let Some(parent_dir) = file_path_obj.parent() {
-                        cmd.arg("-o").arg(parent_dir);
-                    }
+// 运行一次工具期间持有的工作目录锁与信号量许可，进程退出或被终止后释放
+type RunPermit = (
+    Option<OwnedMutexGuard<()>>,
+    Option<OwnedSemaphorePermit>,
+    Option<OwnedSemaphorePermit>,
+    OwnedSemaphorePermit,
+);
+
+impl RunSlots {
+    // 写入共享输出的工具先获取工作目录锁，同一目录的文件逐个运行，不同目录仍并行
+    // 再依次获取作业、工具与全局信号量许可；等待期间作业被取消则返回 None
+    async fn acquire(
+        &self,
+        job: &PreparedJob,
+        file_path: &str,
+        cancel: &mut CancelToken,
+    ) -> Option<RunPermit> {
+        let shared_dir = job.shared_work_dir(file_path);
+        let permit = tokio::select! {
+            permit = async {
+                let dir_guard = match &shared_dir {
+                    Some(dir) => Some(jobs::lock_dir(dir).await),
+                    None => None,
+                };
+                let job_permit = match &self.job {
+                    Some(job_semaphore) => Some(job_semaphore.clone().acquire_owned().await.ok()?),
+                    None => None,
+                };
+                let tool_permit = match &self.tool {
+                    Some(tool_semaphore) => Some(tool_semaphore.clone().acquire_owned().await.ok()?),
+                    None => None,
+                };
+                let permit = self.global.clone().acquire_owned().await.ok()?;
+                Some((dir_guard, job_permit, tool_permit, permit))
+            } => permit,
+            _ = cancel.cancelled() => None,
+        };
+        permit.filter(|_| !cancel.is_cancelled())
+    }
+}
 
-                    // 添加语言参数（与当前界面语言一致）
-                    if lang == "zh" {
-                        cmd.arg("-l");
-                    }
+// 按作业的重试策略处理单个文件
+// 每次尝试前获取目录锁与信号量许可，尝试结束即释放，等待重试期间其他文件可以运行
+// 失败的错误类型可重试时等待后再次运行，此前失败的尝试记录在结果的 previous_attempts 中
+async fn process_file_with_retry(
+    job: &PreparedJob,
+    file_path: &str,
+    slots: &RunSlots,
+    mut cancel: CancelToken,
+    job_id: &str,
+    index: usize,
+    events: &EventSink,
+) -> ProcessResult {
+    let mut previous_attempts = Vec::new();
+    // 等待重试的上一次失败结果
+    let mut failed: Option<ProcessResult> = None;
+    let mut attempt = 1;
+    loop {
+        let Some(permit) = slots.acquire(job, file_path, &mut cancel).await else {
+            // 未能开始首次尝试时为未开始；重试前被取消则保留上一次失败的结果
+            return match failed {
+                Some(mut result) => {
+                    result.previous_attempts = previous_attempts;
+                    result
                 }
-
-                // 为 SMNFiler_v2 添加特殊参数
-                if let Tool::SMNFilerV2 = tool {
-                    // 添加开发者模式参数
-                    cmd.arg("-dev");
+                None => ProcessResult::new(file_path, ResultCode::NotStarted, &job.language),
+            };
+        };
+        if let Some(failed) = failed.take() {
+            previous_attempts.push(AttemptRecord::new(attempt - 1, failed));
+        } else {
+            events(JobEvent::FileStarted {
+                job_id: job_id.to_string(),
+                index,
+                file_path: file_path.to_string(),
+            });
+        }
+        let mut result = process_file(job, file_path, cancel.clone(), job_id, index, attempt).await;
+        drop(permit);
+
+        let retry = !cancel.is_cancelled()
+            && result
+                .failure
+                .as_ref()
+                .is_some_and(|e| job.retry.should_retry(attempt, e.kind()));
+        if retry {
+            // 等待期间作业被取消则保留本次失败的结果
+            tokio::select! {
+                _ = tokio::time::sleep(job.retry.backoff(attempt)) => {
+                    failed = Some(result);
+                    attempt += 1;
+                    continue;
                 }
+                _ = cancel.cancelled() => {}
+            }
+        }
 
-                // 为 UPDFiler_v1 添加特殊参数
-                if let Tool::UpdfilerV1 = tool {
-                    // 添加输出路径参数（使用输入文件所在目录）
-                    if let Some(parent_dir) = file_path_obj.parent() {
-                        cmd.arg("-o").arg(parent_dir);
-                    }
-                }
+        result.previous_attempts = previous_attempts;
+        return result;
+    }
+}
 
-                // 为 UPDFiler_v2 添加 -dev 参数
-                if let Tool::UpdfilerV2 = tool {
-                    if verbose_log.unwrap_or(false) {
-                        cmd.arg("-dev");
-                    }
-                }
+// 处理单个文件（一次尝试）
+async fn process_file(
+    job: &PreparedJob,
+    file_path: &str,
+    cancel: CancelToken,
+    job_id: &str,
+    index: usize,
+    attempt: u32,
+) -> ProcessResult {
+    let lang = job.language.as_str();
+    let file_path_obj = Path::new(file_path);
+
+    // 检查文件是否存在
+    if !file_path_obj.exists() {
+        let error = ProcessError::FileNotFound {
+            file: file_path.to_string(),
+        };
+        return ProcessResult::new(file_path, ResultCode::FileNotFound, lang)
+            .with_error(error, lang);
+    }
 
-                // 在开发模式下输出调用命令到控制台
-                #[cfg(debug_assertions)]
-                {
-                    let cmd_str = format!("{:?}", cmd);
-                    println!("[DEBUG] Executing command: {}", cmd_str);
-                    println!("[DEBUG] Working directory: {:?}", file_dir);
-                    println!("[DEBUG] Tool: {}, File: {}", _tool_name, file_path);
-                    if let Tool::AneuFiler | Tool::Aneu23 | Tool::SHCarrier = tool {
-                        println!("[DEBUG] Added default -dev parameter for AneuFiler/Aneu23/SHCarrier");
-                    }
-                    if use_area_data && !matches!(tool, Tool::UpdfilerV1 | Tool::UpdfilerV2) {
-                        println!("[DEBUG] Using peak area data: true");
-                    }
-                    if let Some(ref std_name) = std_sample_name {
-                        println!("[DEBUG] Standard sample name: {}", std_name);
-                    }
-                    if matches!(tool, Tool::SMNFilerV1 | Tool::SMNFilerV2 | Tool::SHCarrier | Tool::UpdfilerV1 | Tool::UpdfilerV2) {
-                        println!("[DEBUG] Windows optimization: {}", windows_optimization.unwrap_or(false));
-                    }
-                    if let Tool::UpdfilerV2 = tool {
-                        println!("[DEBUG] Verbose log: {}", verbose_log.unwrap_or(false));
-                    }
-                    println!("[DEBUG] ----------------------------------------");
+    // 沙箱模式或指定输出目录时在暂存目录中运行，结束后将生成的文件移入目标目录
+    // 沙箱模式未指定输出目录时移回输入文件所在目录，同名文件改名，不覆盖原有的仪器导出文件
+    // 及同一目录中并行运行的其他文件生成的同名文件
+    let destination = match (&job.run.output, job.run.sandbox) {
+        (Some(output), _) => Some(output.clone()),
+        (None, true) => Some(OutputOptions {
+            dir: file_path_obj
+                .parent()
+                .unwrap_or(Path::new("."))
+                .to_path_buf(),
+            collision: CollisionPolicy::Rename,
+        }),
+        (None, false) => None,
+    };
+    let staging = match &destination {
+        Some(_) => match staging::StagingDir::create(job_id, index) {
+            Ok(staging) => Some(staging),
+            Err(e) => {
+                return ProcessResult::new(file_path, ResultCode::OutputFailed, lang)
+                    .with_error(e.into(), lang);
+            }
+        },
+        None => None,
+    };
+
+    // 沙箱模式及不支持 {output_dir} 的工具处理复制到暂存目录的副本，其余工具直接读取原文件
+    let input = match &staging {
+        Some(staging) if job.run.sandbox || !job.build.def.supports_output_dir() => {
+            match staging.stage_input(file_path_obj) {
+                Ok(staged) => staged,
+                Err(e) => {
+                    return ProcessResult::new(file_path, ResultCode::OutputFailed, lang)
+                        .with_error(e.into(), lang);
                 }
+            }
+        }
+        _ => file_path_obj.to_path_buf(),
+    };
+
+    // 工作目录：暂存目录或输入文件所在目录
+    let work_dir = match &staging {
+        Some(staging) => staging.path(),
+        None => file_path_obj.parent().unwrap_or(Path::new(".")),
+    };
+
+    // 根据工具清单构建命令行参数
+    let mut cmd = tokio::process::Command::new(&job.exe_path);
+    cmd.args(
+        job.build
+            .def
+            .build_args(&input, Some(work_dir), &job.options, lang),
+    )
+    .current_dir(work_dir);
 
-                // 执行外部程序
-                match cmd.current_dir(file_dir).output() {
-                    Ok(output) => {
-                        if output.status.success() {
-                            ProcessResult {
-                                success: true,
-                                message: get_message("process_success", &lang, Some(&file_path_obj.file_name().unwrap().to_string_lossy())),
-                                error: None,
-                                file_path: Some(file_path.clone()),
-                            }
-                        } else {
-                            let error_msg = String::from_utf8_lossy(&output.stderr);
-                            let error = ProcessError::CommandFailed {
-                                message: error_msg.to_string(),
-                            };
-                            ProcessResult {
-                                success: false,
-                                message: get_message("process_failed", &lang, Some(&file_path_obj.file_name().unwrap().to_string_lossy())),
-                                error: Some(process_error_to_localized_string(&error, &lang)),
-                                file_path: Some(file_path.clone()),
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        let error = ProcessError::CommandFailed {
-                            message: e.to_string(),
-                        };
-                        ProcessResult {
-                            success: false,
-                            message: get_message("execute_failed", &lang, Some(&file_path_obj.file_name().unwrap().to_string_lossy())),
-                            error: Some(process_error_to_localized_string(&error, &lang)),
-                            file_path: Some(file_path.clone()),
-                        }
-                    }
-                }
-            });
+    // 在开发模式下输出调用命令到标准错误输出（不影响命令行模式输出的 JSON）
+    #[cfg(debug_assertions)]
+    {
+        let cmd_str = format!("{:?}", cmd.as_std());
+        eprintln!("[DEBUG] Executing command: {}", cmd_str);
+        eprintln!("[DEBUG] Working directory: {:?}", work_dir);
+        eprintln!(
+            "[DEBUG] Tool: {} ({}), File: {}",
+            job.build.def.name, job.build.version, file_path
+        );
+        eprintln!("[DEBUG] Options: {:?}", job.options);
+        eprintln!("[DEBUG] ----------------------------------------");
+    }
 
-            // 使用超时包装任务执行，防止外部工具卡死
-            match timeout(Duration::from_secs(PROCESS_TIMEOUT_SECS), task_handle).await {
-                Ok(Ok(result)) => result,
-                Ok(Err(e)) => ProcessResult {
-                    success: false,
-                    message: get_message("task_execution_failed", &lang_for_timeout, None),
-                    error: Some(e.to_string()),
-                    file_path: None,
-                },
-                Err(_) => ProcessResult {
-                    success: false,
-                    message: get_message("task_execution_failed", &lang_for_timeout, None),
-                    error: Some(format!("Timeout after {} seconds", PROCESS_TIMEOUT_SECS)),
-                    file_path: None,
-                },
+    // 运行前记录工作目录状态，运行后据此找出工具生成的文件
+    let before = outputs::snapshot(work_dir).await;
+
+    // 执行外部程序，超时或作业取消时终止工具进程及其子进程
+    // 超出内存上限的工具输出保存到 <logs>/<job_id>/<index>.stdout.log 等文件
+    // 重试时为 <index>.retry<N>.stdout.log，保留每次尝试的输出
+    let log_name = match attempt {
+        1 => index.to_string(),
+        _ => format!("{}.retry{}", index, attempt - 1),
+    };
+    let log_prefix = paths::run_log_dir().join(job_id).join(log_name);
+    let mut record = ExecutionRecord::new(&cmd);
+    let limit = Duration::from_secs(job.timeout_secs);
+    let outcome = runner::run(cmd, limit, cancel, &log_prefix, &mut record).await;
+    // 直接在输入文件所在目录中运行、且未按目录逐个运行的工具，同一目录中其他文件的运行可能同时生成文件，
+    // 只保留以本输入文件命名的文件
+    let mut output_files = match outcome {
+        Ok(_) => {
+            let changed = outputs::snapshot(work_dir)
+                .await
+                .changed_since(&before, &input);
+            if staging.is_none() && !job.build.def.shared_outputs {
+                outputs::named_after(changed, work_dir, &input)
+            } else {
+                changed
             }
         }
-    }).collect();
+        Err(_) => Vec::new(),
+    };
+    let result = match outcome {
+        Ok(RunOutcome::Exited(status)) if status.success() => {
+            ProcessResult::new(file_path, ResultCode::Success, lang)
+        }
+        Ok(RunOutcome::Exited(status)) => {
+            let error = ProcessError::exited(status, &record);
+            let result = match &error {
+                ProcessError::Signaled { signal, .. } => {
+                    ProcessResult::new(file_path, ResultCode::Signaled, lang)
+                        .with_param("signal", signal)
+                }
+                _ => ProcessResult::new(file_path, ResultCode::NonzeroExit, lang)
+                    .with_param("exit_code", status.code().unwrap_or(-1)),
+            };
+            result.with_error(error, lang)
+        }
+        Ok(RunOutcome::TimedOut) => {
+            let error = ProcessError::timed_out(job.timeout_secs, &record);
+            ProcessResult::new(file_path, ResultCode::Timeout, lang)
+                .with_param("seconds", job.timeout_secs)
+                .with_error(error, lang)
+        }
+        Ok(RunOutcome::Cancelled) => ProcessResult::new(file_path, ResultCode::Cancelled, lang),
+        Err(e) => ProcessResult::new(file_path, ResultCode::SpawnFailed, lang)
+            .with_param("detail", &e)
+            .with_error(ProcessError::spawn_failed(e, &job.exe_path), lang),
+    };
+
+    // 将暂存目录中生成的文件按同名处理方式移入目标目录
+    let result = match (&staging, &destination) {
+        (Some(staging), Some(output)) => {
+            match outputs::place(output_files, staging.path(), output).await {
+                Ok(placed) => {
+                    output_files = placed;
+                    result
+                }
+                Err(e) => {
+                    output_files = Vec::new();
+                    ProcessResult::new(file_path, ResultCode::OutputFailed, lang)
+                        .with_error(e.into(), lang)
+                }
+            }
+        }
+        _ => result,
+    };
+    result
+        .with_execution(record)
+        .with_output_files(output_files)
+}
 
-    // 等待所有任务完成并收集结果
-    for task in tasks {
-        let result = task.await;
-        results.push(result);
-    }
+// 展开输入路径的命令：目录递归展开为其中符合筛选规则的文件，文件原样保留
+// 返回去重后的文件列表，供运行前预览及作为 file_paths 提交
+// include/exclude 缺省时使用工具定义中的默认筛选规则（未指定工具时包含全部文件）
+#[tauri::command]
+fn expand_inputs(
+    paths: Vec<String>,
+    tool_name: Option<String>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    language: Option<String>,
+) -> Result<Vec<String>, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    let defaults = match &tool_name {
+        Some(tool) => registry::current()
+            .find(tool, None)
+            .map(|build| build.def.input_filters.clone())
+            .map_err(|e| process_error_to_localized_string(&e, lang))?,
+        None => inputs::InputFilters::default(),
+    };
+    let filters = defaults.overridden(include, exclude);
+    inputs::expand(&paths, &filters).map_err(|e| process_error_to_localized_string(&e, lang))
+}
+
+// 启动处理作业的命令，立即返回作业 ID
+// options 中的键与 tools.json 中声明的选项一一对应，缺省时使用清单默认值
+// version 指定运行的工具构建版本，缺省时使用默认构建
+// run 指定输出目录、沙箱模式等运行方式，缺省时结果写在输入文件旁
+#[tauri::command]
+async fn start_job(
+    app: tauri::AppHandle,
+    tool_name: String,
+    version: Option<String>,
+    file_paths: Vec<String>,
+    options: Option<Map<String, Value>>,
+    run: Option<RunOptions>,
+    language: Option<String>,
+) -> Result<String, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    start_job_internal(
+        app,
+        tool_name,
+        version,
+        file_paths,
+        options.unwrap_or_default(),
+        run.unwrap_or_default(),
+        language.clone(),
+    )
+    .await
+    .map_err(|e| process_error_to_localized_string(&e, lang))
+}
+
+// 等待作业结束并返回全部结果的命令
+#[tauri::command]
+async fn wait_job(job_id: String, language: Option<String>) -> Result<Vec<ProcessResult>, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    jobs::wait(&job_id).await.ok_or_else(|| {
+        process_error_to_localized_string(&ProcessError::UnknownJob { job: job_id }, lang)
+    })
+}
 
-    Ok(results)
+// 取消作业的命令：未开始的文件不再启动，运行中的工具进程被终止
+// 作业不存在或已结束时返回 false
+#[tauri::command]
+fn cancel_job(job_id: String) -> bool {
+    jobs::cancel(&job_id)
 }
 
-// 处理文件的命令
+// 处理文件的命令：启动作业并等待全部结果
 #[tauri::command]
 async fn process_files(
     app: tauri::AppHandle,
     tool_name: String,
+    version: Option<String>,
     file_paths: Vec<String>,
-    use_area_data: bool,
-    std_sample_name: Option<String>,
-    windows_optimization: Option<bool>,
-    verbose_log: Option<bool>,
+    options: Option<Map<String, Value>>,
+    run: Option<RunOptions>,
     language: Option<String>,
-    tolerance: Option<f64>,
 ) -> Result<Vec<ProcessResult>, String> {
-    let lang = language.as_deref().unwrap_or("en");
-    process_files_internal(
+    let job_id = start_job(
         app,
         tool_name,
+        version,
         file_paths,
-        use_area_data,
-        std_sample_name,
-        windows_optimization,
-        verbose_log,
+        options,
+        run,
         language.clone(),
-        tolerance,
+    )
+    .await?;
+    wait_job(job_id, language).await
+}
+
+// 提交批次到作业队列，返回批次 ID；参数与 start_job 相同
+// 批次按队列顺序逐个运行，运行中的批次可通过其 job_id 调用 cancel_job 取消
+#[tauri::command]
+fn enqueue_batch(
+    app: tauri::AppHandle,
+    tool_name: String,
+    version: Option<String>,
+    file_paths: Vec<String>,
+    options: Option<Map<String, Value>>,
+    run: Option<RunOptions>,
+    language: Option<String>,
+) -> String {
+    let id = queue::submit(
+        tool_name,
+        version,
+        file_paths,
+        options.unwrap_or_default(),
+        run.unwrap_or_default(),
+        language.unwrap_or_else(|| "en".to_string()),
+        None,
+    );
+    emit_queue_changed(&app);
+    id
+}
+
+// 获取作业队列中的全部批次
+#[tauri::command]
+fn list_queue() -> Vec<queue::QueueBatch> {
+    queue::list()
+}
+
+// 调整批次在队列中的位置，批次不存在时返回 false
+#[tauri::command]
+fn move_batch(app: tauri::AppHandle, batch_id: String, position: usize) -> bool {
+    let moved = queue::move_to(&batch_id, position);
+    emit_queue_changed(&app);
+    moved
+}
+
+// 从队列中移除批次，运行中的批次先取消其作业
+#[tauri::command]
+fn remove_batch(app: tauri::AppHandle, batch_id: String) -> bool {
+    let Some(batch) = queue::remove(&batch_id) else {
+        return false;
+    };
+    if let Some(job_id) = &batch.job_id {
+        jobs::cancel(job_id);
+    }
+    emit_queue_changed(&app);
+    true
+}
+
+// 继续处理已取消、已中断或未能启动的批次，仅运行尚未结束的文件
+#[tauri::command]
+fn resume_batch(app: tauri::AppHandle, batch_id: String) -> bool {
+    let resumed = queue::resume(&batch_id);
+    emit_queue_changed(&app);
+    resumed
+}
+
+// 移除全部已结束的批次
+#[tauri::command]
+fn clear_finished_batches(app: tauri::AppHandle) {
+    queue::clear_finished();
+    emit_queue_changed(&app);
+}
+
+// 添加监视文件夹，返回监视 ID；文件夹中导出完成的新文件自动提交到作业队列处理
+// config 中的工具、参数与运行方式同 start_job，process_existing 为 true 时同时处理文件夹中已有的文件
+#[tauri::command]
+fn add_watch(
+    app: tauri::AppHandle,
+    config: watch::WatchConfig,
+    process_existing: Option<bool>,
+    language: Option<String>,
+) -> Result<String, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    let id = watch::add(config, process_existing.unwrap_or(false))
+        .map_err(|e| process_error_to_localized_string(&e, lang))?;
+    emit_watches_changed(&app);
+    Ok(id)
+}
+
+// 获取全部监视文件夹
+#[tauri::command]
+fn list_watches() -> Vec<watch::WatchInfo> {
+    watch::list()
+}
+
+// 移除监视文件夹，已提交的批次不受影响
+#[tauri::command]
+fn remove_watch(app: tauri::AppHandle, watch_id: String) -> bool {
+    let removed = watch::remove(&watch_id);
+    emit_watches_changed(&app);
+    removed
+}
+
+// 暂停或恢复监视文件夹
+#[tauri::command]
+fn set_watch_enabled(app: tauri::AppHandle, watch_id: String, enabled: bool) -> bool {
+    let updated = watch::set_enabled(&watch_id, enabled);
+    emit_watches_changed(&app);
+    updated
+}
+
+// 获取全部已保存的流水线
+#[tauri::command]
+fn list_pipelines() -> Vec<pipelines::Pipeline> {
+    pipelines::list()
+}
+
+// 保存流水线，同名流水线被替换；工具、版本或选项无效时不保存
+#[tauri::command]
+fn save_pipeline(pipeline: pipelines::Pipeline, language: Option<String>) -> Result<(), String> {
+    let lang = language.as_deref().unwrap_or("en");
+    pipelines::save(pipeline).map_err(|e| process_error_to_localized_string(&e, lang))
+}
+
+// 删除流水线，不存在时返回 false
+#[tauri::command]
+fn delete_pipeline(name: String, language: Option<String>) -> Result<bool, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    pipelines::delete(&name).map_err(|e| process_error_to_localized_string(&e, lang))
+}
+
+// 启动流水线作业的命令，返回作业 ID；可与 start_job 的作业一样等待结果或取消
+#[tauri::command]
+async fn start_pipeline(
+    app: tauri::AppHandle,
+    name: String,
+    file_paths: Vec<String>,
+    run: Option<RunOptions>,
+    language: Option<String>,
+) -> Result<String, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    start_pipeline_internal(
+        app,
+        name,
+        file_paths,
+        run.unwrap_or_default(),
+        lang.to_string(),
     )
     .await
     .map_err(|e| process_error_to_localized_string(&e, lang))
 }
 
-// 清理所有临时文件
-fn cleanup_temp_files() {
-    let temp_dir = std::env::temp_dir();
+// 获取应用设置
+#[tauri::command]
+fn get_settings() -> settings::AppSettings {
+    settings::current()
+}
 
-    // 清理所有 cmtools_ 开头的临时文件
-    if let Ok(entries) = fs::read_dir(&temp_dir) {
-        for entry in entries.flatten() {
-            if let Some(file_name) = entry.file_name().to_str() {
-                if file_name.starts_with("cmtools_") {
-                    let _ = fs::remove_file(entry.path());
-                }
-            }
-        }
-    }
+// 保存应用设置并重新加载外部工具，返回加载外部工具时遇到的问题
+#[tauri::command]
+fn update_settings(
+    new_settings: settings::AppSettings,
+    language: Option<String>,
+) -> Result<Vec<String>, String> {
+    let lang = language.as_deref().unwrap_or("en");
+    settings::save(new_settings).map_err(|e| process_error_to_localized_string(&e, lang))?;
+    Ok(registry::reload().problems.clone())
+}
+
+// 重新扫描外部工具目录，返回加载外部工具时遇到的问题
+#[tauri::command]
+fn reload_tools() -> Vec<String> {
+    registry::reload().problems.clone()
+}
+
+// 关闭时清理：回收工具缓存，删除过期的输出日志及遗留的暂存目录
+fn cleanup_on_close() {
+    // 仅回收当前安装不再使用的工具缓存，其他实例及其他安装的缓存不受影响
+    let _ = cache::collect_garbage();
+    let _ = runner::remove_expired_logs();
+    let _ = staging::remove_stale();
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -811,9 +2168,50 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             process_files,
             open_file_directory,
-            get_tool_version
+            open_output_file,
+            get_tool_version,
+            list_tools,
+            expand_inputs,
+            start_job,
+            wait_job,
+            cancel_job,
+            enqueue_batch,
+            list_queue,
+            move_batch,
+            remove_batch,
+            resume_batch,
+            clear_finished_batches,
+            add_watch,
+            list_watches,
+            remove_watch,
+            set_watch_enabled,
+            list_pipelines,
+            save_pipeline,
+            delete_pipeline,
+            start_pipeline,
+            get_settings,
+            update_settings,
+            reload_tools
         ])
         .setup(|app| {
+            // 启动作业队列调度，继续运行上次退出时仍在排队的批次
+            tauri::async_runtime::spawn(run_queue(app.app_handle().clone()));
+            // 启动监视文件夹扫描
+            tauri::async_runtime::spawn(run_watches(app.app_handle().clone()));
+            // 设置中启用接口服务时在本机回环地址上启动，端口被占用等错误不影响界面使用
+            let api_settings = settings::current().api;
+            if api_settings.enabled {
+                tauri::async_runtime::spawn(async move {
+                    let result = match api::ensure_token() {
+                        Ok(token) => api::serve(api_settings.port, token).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Failed to start API server: {}", e);
+                    }
+                });
+            }
+
             let window = app.get_webview_window("main").unwrap();
             let window_clone = window.clone();
             let cleanup = std::sync::Arc::new(std::sync::Mutex::new(false));
@@ -823,7 +2221,7 @@ pub fn run() {
                 if let tauri::WindowEvent::CloseRequested { api: _, .. } = event {
                     if !*cleanup_clone.lock().unwrap() {
                         *cleanup_clone.lock().unwrap() = true;
-                        cleanup_temp_files();
+                        cleanup_on_close();
                         let _ = window_clone.close();
                     }
                 }
@@ -833,3 +2231,70 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::fs;
+
+    #[test]
+    fn changed_executable_is_detected_and_rewritten() {
+        let dir = std::env::temp_dir().join(format!("cmtools-extract-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let exe_path = dir.join("AneuFiler");
+        let data = b"#!/bin/sh\necho AneuFiler 1.0\n";
+        let sha256 = format!("{:x}", Sha256::digest(data));
+
+        // 尚未释放
+        assert!(should_rewrite_exe(&exe_path, &sha256));
+        cache::write_executable_atomic(&exe_path, data).unwrap();
+        assert!(!should_rewrite_exe(&exe_path, &sha256));
+
+        // 大小不变、内容被改动的文件同样需要重新写入
+        let mut tampered = data.to_vec();
+        tampered[10] ^= 1;
+        fs::write(&exe_path, &tampered).unwrap();
+        assert!(should_rewrite_exe(&exe_path, &sha256));
+
+        cache::write_executable_atomic(&exe_path, data).unwrap();
+        assert_eq!(fs::read(&exe_path).unwrap(), data);
+        assert!(!should_rewrite_exe(&exe_path, &sha256));
+        // 临时文件已重命名为目标文件
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn resized_limits_count_permits_already_held() {
+        let semaphore = Arc::new(Semaphore::new(2));
+        let mut sized = (2, semaphore.clone());
+        let first = semaphore.clone().acquire_owned().await.unwrap();
+        let second = semaphore.clone().acquire_owned().await.unwrap();
+
+        // 两个进程运行中时上限改为 1：两个都结束后才有新的许可
+        resize_semaphore(&mut sized, 1);
+        assert_eq!(sized.0, 1);
+        drop(first);
+        tokio::task::yield_now().await;
+        assert_eq!(semaphore.available_permits(), 0);
+        drop(second);
+        tokio::task::yield_now().await;
+        assert_eq!(semaphore.available_permits(), 1);
+
+        resize_semaphore(&mut sized, 3);
+        assert_eq!(semaphore.available_permits(), 3);
+        resize_semaphore(&mut sized, 2);
+        assert_eq!(semaphore.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn tool_limit_keeps_one_semaphore_per_tool() {
+        let first = tool_concurrency_limit("ResizeTestTool", 2);
+        let _running = first.clone().acquire_owned().await.unwrap();
+        let second = tool_concurrency_limit("ResizeTestTool", 3);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second.available_permits(), 2);
+    }
+}
@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // 带子命令启动时以命令行模式运行，不打开窗口（如 cmtools run --tool Aneu23 ...）
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cmtools_lib::run_cli(args) {
+        std::process::exit(code);
+    }
     cmtools_lib::run()
 }
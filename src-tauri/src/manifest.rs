@@ -0,0 +1,535 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::ProcessError;
+use crate::inputs::InputFilters;
+use crate::settings::RunLimits;
+
+// 由 build.rs 根据 tools.json 生成的嵌入二进制表
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_tools.rs"));
+}
+
+// 工具清单，描述每个工具的可执行文件、选项及命令行参数映射
+// 新增工具只需修改 tools.json 并放入对应二进制文件，无需改动代码
+#[derive(Debug, Deserialize)]
+pub(crate) struct ToolManifest {
+    pub defaults: ManifestDefaults,
+    pub tools: Vec<ToolDef>,
+}
+
+// 清单级默认值，工具定义与设置均未指定时使用
+#[derive(Debug, Deserialize)]
+pub(crate) struct ManifestDefaults {
+    // 单个文件的处理超时时间（秒）
+    pub timeout_secs: u64,
+}
+
+// 单个工具的定义
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ToolDef {
+    // 工具名称（前端传入的标识）
+    pub name: String,
+    // 界面显示名称，缺省时使用工具名称
+    #[serde(default)]
+    pub label: Option<String>,
+    // 按语言区分的工具说明，如 {"zh": "...", "en": "..."}
+    #[serde(default)]
+    pub description: HashMap<String, String>,
+    // 可执行文件基础名（不含平台扩展名）
+    pub binary: String,
+    #[serde(default)]
+    pub options: Vec<OptionDef>,
+    // 按顺序展开的命令行参数模板
+    #[serde(default)]
+    pub args: Vec<ArgTemplate>,
+    // 工具会在工作目录中写入固定文件名的日志或汇总（如 -dev 日志），
+    // 同一目录中的多个文件并发运行会互相覆盖，需按目录逐个运行
+    #[serde(default)]
+    pub shared_outputs: bool,
+    // 工具的默认并发上限与超时，如内存占用大的工具限制并发、耗时长的工具延长超时
+    // 并发上限由该工具的所有作业共享
+    #[serde(default)]
+    pub limits: RunLimits,
+    // 选择目录作为输入时默认的文件筛选规则
+    #[serde(default)]
+    pub input_filters: InputFilters,
+}
+
+// 选项类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OptionKind {
+    Bool,
+    String,
+    Number,
+}
+
+// 工具选项定义，同时作为前端渲染选项控件的 schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OptionDef {
+    pub key: String,
+    // 命令行模式中的简短参数名，如 std 对应 --std；选项键（如 --std-sample-name）始终可用
+    #[serde(default)]
+    pub cli_flag: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: OptionKind,
+    #[serde(default)]
+    pub default: Value,
+    // 数值选项的取值范围（闭区间）及输入步长
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub step: Option<f64>,
+    // 前端翻译表中的标签、说明及占位提示键
+    #[serde(default)]
+    pub label_key: Option<String>,
+    #[serde(default)]
+    pub description_key: Option<String>,
+    #[serde(default)]
+    pub placeholder_key: Option<String>,
+}
+
+// 命令行参数模板
+// `when` 引用选项：布尔为 true、字符串非空、数值非 0 时才展开
+// `when_language` 仅在界面语言匹配时展开
+// 参数中整段为 `{input}`、`{input_dir}`、`{output_dir}`、`{value}` 的占位符会被替换
+// 使用 `{output_dir}` 的工具支持指定输出目录，未指定时该占位符等同于 `{input_dir}`
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ArgTemplate {
+    #[serde(default)]
+    pub when: Option<String>,
+    #[serde(default)]
+    pub when_language: Option<String>,
+    pub args: Vec<String>,
+}
+
+// 返回给前端的工具信息
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ToolInfo {
+    pub name: String,
+    pub label: String,
+    pub description: HashMap<String, String>,
+    // 工具 --version 输出
+    pub version: Option<String>,
+    // 默认构建的版本及全部可用版本（内置构建为 builtin）
+    pub default_version: String,
+    pub versions: Vec<String>,
+    pub options: Vec<OptionDef>,
+    // 工具定义中的默认并发上限与超时
+    pub limits: RunLimits,
+    // 选择目录作为输入时默认的文件筛选规则
+    pub input_filters: InputFilters,
+}
+
+// 已校验并补全默认值的选项集合
+pub(crate) type ResolvedOptions = HashMap<String, Value>;
+
+// 内置工具清单，编译时嵌入
+static TOOL_MANIFEST: LazyLock<ToolManifest> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../tools.json")).expect("invalid tools.json manifest")
+});
+
+// 获取全部内置工具定义
+pub(crate) fn tools() -> &'static [ToolDef] {
+    &TOOL_MANIFEST.tools
+}
+
+// 获取清单级默认值
+pub(crate) fn defaults() -> &'static ManifestDefaults {
+    &TOOL_MANIFEST.defaults
+}
+
+impl ToolDef {
+    // 获取可执行文件名（根据平台返回不同文件名）
+    pub fn exe_name(&self) -> String {
+        #[cfg(target_os = "windows")]
+        {
+            format!("{}.exe", self.binary)
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            self.binary.clone()
+        }
+    }
+
+    // 获取嵌入的可执行文件数据及其构建时计算的 SHA-256
+    pub fn embedded_binary(&self) -> Option<(&'static [u8], &'static str)> {
+        embedded::EMBEDDED_BINARIES
+            .iter()
+            .find(|(binary, _, _)| *binary == self.binary)
+            .map(|(_, data, sha256)| (*data, *sha256))
+    }
+
+    // 生成返回给前端的工具信息
+    pub fn info(
+        &self,
+        version: Option<String>,
+        default_version: String,
+        versions: Vec<String>,
+    ) -> ToolInfo {
+        ToolInfo {
+            name: self.name.clone(),
+            label: self.label.clone().unwrap_or_else(|| self.name.clone()),
+            description: self.description.clone(),
+            version,
+            default_version,
+            versions,
+            options: self.options.clone(),
+            limits: self.limits,
+            input_filters: self.input_filters.clone(),
+        }
+    }
+
+    // 是否声明了某个选项
+    pub fn has_option(&self, key: &str) -> bool {
+        self.options.iter().any(|option| option.key == key)
+    }
+
+    // 按命令行参数名查找选项：简短参数名或选项键（下划线可写作连字符），不区分大小写
+    pub fn cli_option(&self, flag: &str) -> Option<&OptionDef> {
+        let flag = flag.to_ascii_lowercase().replace('-', "_");
+        self.options.iter().find(|option| {
+            option.key == flag
+                || option
+                    .cli_flag
+                    .as_deref()
+                    .is_some_and(|cli_flag| cli_flag.to_ascii_lowercase().replace('-', "_") == flag)
+        })
+    }
+
+    // 校验调用方传入的选项并补全默认值
+    // null 视为未提供；未声明的选项、类型不符或超出范围均报错
+    pub fn resolve_options(
+        &self,
+        provided: &Map<String, Value>,
+    ) -> Result<ResolvedOptions, ProcessError> {
+        if let Some(key) = provided.keys().find(|key| !self.has_option(key)) {
+            return Err(self.invalid_option(key, "not supported by this tool"));
+        }
+
+        let mut resolved = HashMap::new();
+        for option in &self.options {
+            let value = match provided.get(&option.key) {
+                Some(Value::Null) | None => option.default.clone(),
+                Some(value) => value.clone(),
+            };
+            if value.is_null() {
+                continue;
+            }
+
+            match (option.kind, &value) {
+                (OptionKind::Bool, Value::Bool(_)) | (OptionKind::String, Value::String(_)) => {}
+                (OptionKind::Number, Value::Number(number)) => {
+                    let number = number.as_f64().unwrap_or_default();
+                    if option.min.is_some_and(|min| number < min)
+                        || option.max.is_some_and(|max| number > max)
+                    {
+                        return Err(self.invalid_option(&option.key, "value out of range"));
+                    }
+                }
+                _ => return Err(self.invalid_option(&option.key, "unexpected value type")),
+            }
+            resolved.insert(option.key.clone(), value);
+        }
+
+        Ok(resolved)
+    }
+
+    // 是否支持通过参数指定输出目录
+    pub fn supports_output_dir(&self) -> bool {
+        self.args
+            .iter()
+            .any(|template| template.args.iter().any(|arg| arg == "{output_dir}"))
+    }
+
+    // 为单个输入文件展开命令行参数，output_dir 缺省为输入文件所在目录
+    pub fn build_args(
+        &self,
+        input: &Path,
+        output_dir: Option<&Path>,
+        options: &ResolvedOptions,
+        language: &str,
+    ) -> Vec<OsString> {
+        let input_dir = input.parent().unwrap_or(Path::new("."));
+        let output_dir = output_dir.unwrap_or(input_dir);
+        let mut args = Vec::new();
+
+        for template in &self.args {
+            if template
+                .when_language
+                .as_deref()
+                .is_some_and(|lang| lang != language)
+            {
+                continue;
+            }
+
+            let value = match &template.when {
+                Some(key) => match options.get(key).and_then(option_arg_value) {
+                    Some(value) => Some(value),
+                    None => continue,
+                },
+                None => None,
+            };
+
+            for arg in &template.args {
+                match arg.as_str() {
+                    "{input}" => args.push(input.as_os_str().to_os_string()),
+                    "{input_dir}" => args.push(input_dir.as_os_str().to_os_string()),
+                    "{output_dir}" => args.push(output_dir.as_os_str().to_os_string()),
+                    "{value}" => args.push(OsString::from(value.clone().unwrap_or_default())),
+                    literal => args.push(OsString::from(literal)),
+                }
+            }
+        }
+
+        args
+    }
+
+    fn invalid_option(&self, option: &str, message: &str) -> ProcessError {
+        ProcessError::InvalidOption {
+            tool: self.name.clone(),
+            option: option.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+// 将选项值转换为参数文本，返回 None 表示该参数不应展开
+fn option_arg_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Bool(true) => Some(String::new()),
+        Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        Value::Number(number) => number
+            .as_f64()
+            .filter(|number| *number != 0.0)
+            .map(|number| number.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const INPUT: &str = "/data/run/S01.txt";
+
+    fn tool(name: &str) -> &'static ToolDef {
+        tools()
+            .iter()
+            .find(|tool| tool.name == name)
+            .unwrap_or_else(|| panic!("{} missing from tools.json", name))
+    }
+
+    // 按工具定义展开参数，选项经 resolve_options 补全默认值
+    fn argv(name: &str, options: Value, language: &str) -> Vec<String> {
+        let def = tool(name);
+        let options = def
+            .resolve_options(options.as_object().unwrap())
+            .expect("options should be valid");
+        def.build_args(Path::new(INPUT), None, &options, language)
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    // 全部选项开启时的参数顺序与改用清单前硬编码的顺序一致
+    #[test]
+    fn argv_matches_baseline_ordering() {
+        let cases = [
+            (
+                "AneuFiler",
+                json!({"use_area_data": true}),
+                vec!["-i", INPUT, "-dev", "-Area"],
+            ),
+            (
+                "Aneu23",
+                json!({"use_area_data": true, "std_sample_name": "STD"}),
+                vec!["-i", INPUT, "-dev", "-Area", "-STD", "STD"],
+            ),
+            (
+                "SMNFiler_v1",
+                json!({"use_area_data": true, "std_sample_name": "STD", "windows_optimization": true}),
+                vec![
+                    "-i",
+                    INPUT,
+                    "-a",
+                    "-c",
+                    "STD",
+                    "-e",
+                    "GBK",
+                    "-o",
+                    "/data/run",
+                    "-l",
+                ],
+            ),
+            (
+                "SMNFiler_v2",
+                json!({"std_sample_name": "STD", "windows_optimization": true}),
+                vec!["-i", INPUT, "-STD", "STD", "-GBK", "-dev"],
+            ),
+            (
+                "SHCarrier",
+                json!({"use_area_data": true, "std_sample_name": "STD", "windows_optimization": true}),
+                vec!["-i", INPUT, "-dev", "-Area", "-STD", "STD", "-GBK"],
+            ),
+            (
+                "UPDFiler_v1",
+                json!({"windows_optimization": true}),
+                vec!["-i", INPUT, "-e", "GBK", "-o", "/data/run"],
+            ),
+            (
+                "UPDFiler_v2",
+                json!({"windows_optimization": true, "verbose_log": true}),
+                vec!["-i", INPUT, "-GBK", "-dev"],
+            ),
+            (
+                "STR-Matcher",
+                json!({"tolerance": 0.5, "windows_optimization": true}),
+                vec!["-i", INPUT, "-t", "0.5", "-GBK"],
+            ),
+        ];
+        for (name, options, expected) in cases {
+            assert_eq!(argv(name, options, "zh"), expected, "{}", name);
+        }
+    }
+
+    // 选项关闭时不展开对应参数
+    #[test]
+    fn argv_with_options_off() {
+        let cases = [
+            ("AneuFiler", json!({}), vec!["-i", INPUT, "-dev"]),
+            (
+                "SMNFiler_v1",
+                json!({"windows_optimization": false, "std_sample_name": ""}),
+                vec!["-i", INPUT, "-o", "/data/run", "-l"],
+            ),
+            (
+                "UPDFiler_v2",
+                json!({"windows_optimization": false}),
+                vec!["-i", INPUT],
+            ),
+            (
+                "STR-Matcher",
+                json!({"windows_optimization": false}),
+                vec!["-i", INPUT],
+            ),
+        ];
+        for (name, options, expected) in cases {
+            assert_eq!(argv(name, options, "zh"), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn when_language_only_applies_to_matching_language() {
+        let options = json!({"windows_optimization": false});
+        assert!(argv("SMNFiler_v1", options.clone(), "zh").contains(&"-l".to_string()));
+        assert!(!argv("SMNFiler_v1", options, "en").contains(&"-l".to_string()));
+    }
+
+    // 数值与改用清单前的 f64::to_string 一致，0 不展开
+    #[test]
+    fn tolerance_formatting() {
+        let cases = [
+            (json!(1), Some("1")),
+            (json!(2.0), Some("2")),
+            (json!(0.25), Some("0.25")),
+            (json!(0), None),
+        ];
+        for (tolerance, expected) in cases {
+            let args = argv(
+                "STR-Matcher",
+                json!({"tolerance": tolerance, "windows_optimization": false}),
+                "en",
+            );
+            let value = args
+                .iter()
+                .position(|arg| arg == "-t")
+                .map(|index| args[index + 1].as_str());
+            assert_eq!(value, expected, "{}", tolerance);
+        }
+    }
+
+    // 空白字符串不展开，其余去除首尾空白
+    #[test]
+    fn string_options_are_trimmed_and_blank_skipped() {
+        let blank = argv("Aneu23", json!({"std_sample_name": "   "}), "en");
+        assert_eq!(blank, vec!["-i", INPUT, "-dev"]);
+        let trimmed = argv("Aneu23", json!({"std_sample_name": " QC1 "}), "en");
+        assert_eq!(trimmed, vec!["-i", INPUT, "-dev", "-STD", "QC1"]);
+    }
+
+    #[test]
+    fn output_dir_placeholder_uses_given_dir() {
+        let def = tool("UPDFiler_v1");
+        let options = def.resolve_options(&Map::new()).unwrap();
+        let args = def.build_args(
+            Path::new(INPUT),
+            Some(Path::new("/staging/1")),
+            &options,
+            "en",
+        );
+        assert_eq!(args[args.len() - 1], OsString::from("/staging/1"));
+        assert!(def.supports_output_dir());
+        assert!(!tool("AneuFiler").supports_output_dir());
+    }
+
+    fn rejection(name: &str, options: Value) -> String {
+        match tool(name).resolve_options(options.as_object().unwrap()) {
+            Err(ProcessError::InvalidOption {
+                option, message, ..
+            }) => format!("{}: {}", option, message),
+            other => panic!("expected InvalidOption, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolve_options_rejects_bad_values() {
+        assert_eq!(
+            rejection("STR-Matcher", json!({"tolerance": -1})),
+            "tolerance: value out of range"
+        );
+        assert_eq!(
+            rejection("STR-Matcher", json!({"tolerance": "0.5"})),
+            "tolerance: unexpected value type"
+        );
+        assert_eq!(
+            rejection("AneuFiler", json!({"use_area_data": "yes"})),
+            "use_area_data: unexpected value type"
+        );
+        assert_eq!(
+            rejection("AneuFiler", json!({"std_sample_name": "STD"})),
+            "std_sample_name: not supported by this tool"
+        );
+    }
+
+    #[test]
+    fn resolve_options_fills_defaults_and_ignores_null() {
+        let def = tool("SMNFiler_v2");
+        let options = def
+            .resolve_options(json!({"std_sample_name": null}).as_object().unwrap())
+            .unwrap();
+        assert_eq!(options.get("std_sample_name"), Some(&json!("STD")));
+        assert_eq!(options.get("windows_optimization"), Some(&json!(true)));
+    }
+
+    #[test]
+    fn cli_option_matches_flag_and_key() {
+        let def = tool("Aneu23");
+        assert_eq!(def.cli_option("area").unwrap().key, "use_area_data");
+        assert_eq!(
+            def.cli_option("use-area-data").unwrap().key,
+            "use_area_data"
+        );
+        assert_eq!(def.cli_option("STD").unwrap().key, "std_sample_name");
+        assert!(def.cli_option("tolerance").is_none());
+    }
+}
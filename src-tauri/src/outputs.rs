@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+// 扫描工作目录的最大深度：0 为工作目录本身，工具通常直接在其中或新建的子目录中输出
+const MAX_SCAN_DEPTH: usize = 2;
+
+// 单次扫描的文件数上限，避免在仪器导出的超大目录中耗时过长
+const MAX_SCAN_FILES: usize = 20_000;
+
+// 输出目录中已存在同名文件时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CollisionPolicy {
+    // 新文件改名为 `名称 (1).扩展名` 等
+    #[default]
+    Rename,
+    // 覆盖已有文件
+    Overwrite,
+    // 保留已有文件，丢弃新文件
+    Skip,
+}
+
+// 输出目录设置：全部工具的结果文件写入该目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OutputOptions {
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub collision: CollisionPolicy,
+}
+
+// 放置输出文件时持有，避免并发任务对同名文件的判断与移动交错
+static PLACE_LOCK: Mutex<()> = Mutex::new(());
+
+// 目录中各文件的修改时间与大小
+#[derive(Debug, Default)]
+pub(crate) struct DirSnapshot {
+    files: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl DirSnapshot {
+    // 扫描目录（不跟随符号链接），无法读取的目录或文件直接跳过
+    fn capture(dir: &Path) -> Self {
+        let mut snapshot = DirSnapshot::default();
+        let mut pending = vec![(dir.to_path_buf(), 0)];
+
+        while let Some((dir, depth)) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    if depth < MAX_SCAN_DEPTH {
+                        pending.push((entry.path(), depth + 1));
+                    }
+                } else if file_type.is_file() {
+                    if snapshot.files.len() >= MAX_SCAN_FILES {
+                        return snapshot;
+                    }
+                    if let Ok(meta) = entry.metadata() {
+                        snapshot
+                            .files
+                            .insert(entry.path(), (meta.modified().ok(), meta.len()));
+                    }
+                }
+            }
+        }
+
+        snapshot
+    }
+
+    // 相对于之前的快照新增或被修改的文件（按路径排序），排除输入文件本身
+    pub fn changed_since(&self, before: &DirSnapshot, input: &Path) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(path, state)| {
+                path.as_path() != input && before.files.get(*path) != Some(state)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        changed.sort();
+        changed
+    }
+}
+
+// 保留以输入文件名（不含扩展名）命名的文件：相对于工作目录的任一级路径以该名称开头，
+// 且其后紧跟非字母数字字符或结束，避免 sample1 认领 sample10_result.xlsx
+// 用于同一目录中并行运行的工具，区分本次运行与其他文件运行生成的文件
+pub(crate) fn named_after(files: Vec<PathBuf>, work_dir: &Path, input: &Path) -> Vec<PathBuf> {
+    let Some(stem) = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+    else {
+        return files;
+    };
+    let matches = |name: &str| {
+        let name = name.to_lowercase();
+        name.strip_prefix(&stem)
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric()))
+    };
+    files
+        .into_iter()
+        .filter(|file| {
+            file.strip_prefix(work_dir)
+                .unwrap_or(file)
+                .components()
+                .any(|component| matches(&component.as_os_str().to_string_lossy()))
+        })
+        .collect()
+}
+
+// 在阻塞线程池中扫描目录，避免慢速网络共享阻塞异步任务
+pub(crate) async fn snapshot(dir: &Path) -> DirSnapshot {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || DirSnapshot::capture(&dir))
+        .await
+        .unwrap_or_default()
+}
+
+// 将暂存目录中生成的文件移入输出目录（保留相对路径），返回移入后的路径
+pub(crate) async fn place(
+    files: Vec<PathBuf>,
+    from: &Path,
+    options: &OutputOptions,
+) -> io::Result<Vec<PathBuf>> {
+    let from = from.to_path_buf();
+    let options = options.clone();
+    tokio::task::spawn_blocking(move || place_blocking(&files, &from, &options))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn place_blocking(
+    files: &[PathBuf],
+    from: &Path,
+    options: &OutputOptions,
+) -> io::Result<Vec<PathBuf>> {
+    let _guard = PLACE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut placed = Vec::new();
+    for file in files {
+        let relative = file.strip_prefix(from).unwrap_or(file);
+        let mut target = options.dir.join(relative);
+        if target.exists() {
+            match options.collision {
+                CollisionPolicy::Skip => continue,
+                CollisionPolicy::Overwrite => {}
+                CollisionPolicy::Rename => target = unique_path(&target),
+            }
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(file, &target)?;
+        placed.push(target);
+    }
+    Ok(placed)
+}
+
+// 在文件名后追加 ` (n)`，直到不与已有文件重名
+fn unique_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+// 移动文件；跨文件系统无法重命名时复制后删除
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_path_appends_first_free_number() {
+        let dir = std::env::temp_dir().join(format!("cmtools-outputs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("S01_result.xlsx");
+        fs::write(&path, "").unwrap();
+        assert_eq!(unique_path(&path), dir.join("S01_result (1).xlsx"));
+
+        fs::write(dir.join("S01_result (1).xlsx"), "").unwrap();
+        assert_eq!(unique_path(&path), dir.join("S01_result (2).xlsx"));
+
+        let no_extension = dir.join("summary");
+        assert_eq!(unique_path(&no_extension), dir.join("summary (1)"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn named_after_keeps_files_of_this_input() {
+        let work_dir = Path::new("/data/run");
+        let files = vec![
+            work_dir.join("S1_result.xlsx"),
+            work_dir.join("S10_result.xlsx"),
+            work_dir.join("s1.log"),
+            work_dir.join("S1/report.pdf"),
+            work_dir.join("summary.xlsx"),
+        ];
+        assert_eq!(
+            named_after(files, work_dir, &work_dir.join("S1.txt")),
+            vec![
+                work_dir.join("S1_result.xlsx"),
+                work_dir.join("s1.log"),
+                work_dir.join("S1/report.pdf"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+// 与 tauri.conf.json 中的 identifier 保持一致，确保界面与后端定位到同一数据目录
+const APP_IDENTIFIER: &str = "com.cmtools.desktop";
+
+// 应用数据目录（与 Tauri 的 app_data_dir 相同）
+// 无法获取系统数据目录时回退到临时目录
+pub(crate) fn app_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(APP_IDENTIFIER)
+}
+
+// 应用设置文件路径
+pub(crate) fn settings_file() -> PathBuf {
+    app_data_dir().join("settings.json")
+}
+
+// 作业队列文件，保存排队及已结束的批次
+pub(crate) fn queue_file() -> PathBuf {
+    app_data_dir().join("queue.json")
+}
+
+// 监视文件夹列表，保存各文件夹的设置及已处理的文件
+pub(crate) fn watches_file() -> PathBuf {
+    app_data_dir().join("watches.json")
+}
+
+// 已保存的流水线列表，保存各流水线的步骤及步骤依次或同时运行（mode）
+// 输出目录、沙箱、并发与超时等运行参数不保存，随每次运行传入
+pub(crate) fn pipelines_file() -> PathBuf {
+    app_data_dir().join("pipelines.json")
+}
+
+// 默认的外部工具目录：<app data>/tools/<name>/<version>/
+pub(crate) fn default_tools_dir() -> PathBuf {
+    app_data_dir().join("tools")
+}
+
+// 内置工具缓存目录：<app data>/cache/tools/<sha256>/
+pub(crate) fn tool_cache_dir() -> PathBuf {
+    app_data_dir().join("cache").join("tools")
+}
+
+// 工具输出日志目录：<app data>/logs/<job_id>/，保存超出内存上限的完整输出
+pub(crate) fn run_log_dir() -> PathBuf {
+    app_data_dir().join("logs")
+}
+
+// 暂存目录：<app data>/staging/<job_id>/<index>/，沙箱模式或指定输出目录时工具在其中运行
+pub(crate) fn staging_dir() -> PathBuf {
+    app_data_dir().join("staging")
+}
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+use crate::ProcessError;
+use crate::paths;
+use crate::registry;
+
+// 流水线中各步骤的运行方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PipelineMode {
+    // 上一步骤的全部文件结束后再运行下一步骤
+    #[default]
+    Sequential,
+    // 各步骤同时运行（仍受并发上限限制）
+    Parallel,
+}
+
+// 流水线中的一个步骤：对全部文件运行一个工具，选项与 start_job 相同
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PipelineStep {
+    pub tool_name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub options: Map<String, Value>,
+}
+
+// 命名流水线，如携带者筛查依次运行 SMNFiler_v2 与 SHCarrier
+// 持久化在 <app data>/pipelines.json，按名称保存与运行；只保存步骤与 mode，运行参数（RunOptions）随每次运行传入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Pipeline {
+    pub name: String,
+    #[serde(default)]
+    pub mode: PipelineMode,
+    pub steps: Vec<PipelineStep>,
+}
+
+static PIPELINES: LazyLock<Mutex<Vec<Pipeline>>> =
+    LazyLock::new(|| Mutex::new(load(&paths::pipelines_file())));
+
+// 读取保存的流水线，文件不存在或无法解析时为空
+fn load(path: &Path) -> Vec<Pipeline> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 获取全部已保存的流水线（按名称排序）
+pub(crate) fn list() -> Vec<Pipeline> {
+    PIPELINES.lock().unwrap().clone()
+}
+
+// 按名称查找流水线
+pub(crate) fn find(name: &str) -> Result<Pipeline, ProcessError> {
+    PIPELINES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|pipeline| pipeline.name == name)
+        .cloned()
+        .ok_or_else(|| ProcessError::UnknownPipeline {
+            name: name.to_string(),
+        })
+}
+
+// 校验流水线：名称非空、至少一个步骤，且各步骤的工具、版本与选项有效
+pub(crate) fn validate(pipeline: &Pipeline) -> Result<(), ProcessError> {
+    let invalid = |message: &str| ProcessError::InvalidPipeline {
+        name: pipeline.name.clone(),
+        message: message.to_string(),
+    };
+    if pipeline.name.trim().is_empty() {
+        return Err(invalid("name is empty"));
+    }
+    if pipeline.steps.is_empty() {
+        return Err(invalid("no steps"));
+    }
+    let registry = registry::current();
+    for step in &pipeline.steps {
+        let build = registry.find(&step.tool_name, step.version.as_deref())?;
+        build.def.resolve_options(&step.options)?;
+    }
+    Ok(())
+}
+
+// 保存流水线，同名流水线被替换；校验失败时不保存
+pub(crate) fn save(mut pipeline: Pipeline) -> Result<(), ProcessError> {
+    pipeline.name = pipeline.name.trim().to_string();
+    validate(&pipeline)?;
+
+    let mut pipelines = PIPELINES.lock().unwrap();
+    let mut updated = pipelines.clone();
+    updated.retain(|existing| existing.name != pipeline.name);
+    updated.push(pipeline);
+    updated.sort_by(|a, b| a.name.cmp(&b.name));
+    write(&paths::pipelines_file(), &updated)?;
+    *pipelines = updated;
+    Ok(())
+}
+
+// 删除流水线，不存在时返回 false
+pub(crate) fn delete(name: &str) -> Result<bool, ProcessError> {
+    let mut pipelines = PIPELINES.lock().unwrap();
+    let mut updated = pipelines.clone();
+    updated.retain(|pipeline| pipeline.name != name);
+    if updated.len() == pipelines.len() {
+        return Ok(false);
+    }
+    write(&paths::pipelines_file(), &updated)?;
+    *pipelines = updated;
+    Ok(true)
+}
+
+// 写入磁盘，先写临时文件再重命名
+fn write(path: &Path, pipelines: &[Pipeline]) -> Result<(), ProcessError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(pipelines).map_err(std::io::Error::other)?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pipeline(name: &str, mode: PipelineMode, tools: &[&str]) -> Pipeline {
+        Pipeline {
+            name: name.to_string(),
+            mode,
+            steps: tools
+                .iter()
+                .map(|tool| PipelineStep {
+                    tool_name: tool.to_string(),
+                    version: None,
+                    options: Map::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn pipelines_survive_a_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("cmtools-pipelines-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("pipelines.json");
+        assert!(load(&path).is_empty());
+
+        let mut carrier = pipeline(
+            "携带者筛查",
+            PipelineMode::Sequential,
+            &["SMNFiler_v2", "SHCarrier"],
+        );
+        carrier.steps[0].version = Some("2.2.4".to_string());
+        carrier.steps[0]
+            .options
+            .insert("std_sample_name".to_string(), json!("STD"));
+        let both = pipeline(
+            "UPD",
+            PipelineMode::Parallel,
+            &["UPDFiler_v1", "UPDFiler_v2"],
+        );
+        write(&path, &[carrier, both]).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let loaded = load(&path);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].name, "携带者筛查");
+        assert_eq!(loaded[0].mode, PipelineMode::Sequential);
+        assert_eq!(loaded[0].steps[0].version.as_deref(), Some("2.2.4"));
+        assert_eq!(loaded[0].steps[0].options["std_sample_name"], json!("STD"));
+        assert_eq!(loaded[0].steps[1].tool_name, "SHCarrier");
+        assert_eq!(loaded[1].mode, PipelineMode::Parallel);
+
+        // 手工编辑的文件可省略 mode、version 与 options
+        fs::write(
+            &path,
+            r#"[{ "name": "AneuFiler only", "steps": [{ "tool_name": "AneuFiler" }] }]"#,
+        )
+        .unwrap();
+        let loaded = load(&path);
+        assert_eq!(loaded[0].mode, PipelineMode::Sequential);
+        assert!(loaded[0].steps[0].options.is_empty());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load(&path).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_empty_names_and_steps() {
+        let unnamed = pipeline("  ", PipelineMode::Sequential, &["AneuFiler"]);
+        let empty = pipeline("Empty", PipelineMode::Sequential, &[]);
+        for pipeline in [unnamed, empty] {
+            assert!(matches!(
+                validate(&pipeline),
+                Err(ProcessError::InvalidPipeline { .. })
+            ));
+        }
+    }
+}
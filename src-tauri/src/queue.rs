@@ -0,0 +1,463 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+use crate::jobs::FileStatus;
+use crate::paths;
+use crate::{ProcessResult, RunOptions};
+
+// 队列中批次的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BatchState {
+    // 等待运行
+    Queued,
+    // 正在运行
+    Running,
+    // 全部文件已运行结束（无论成功与否）
+    Completed,
+    // 运行中被取消，可继续处理未完成的文件
+    Cancelled,
+    // 应用退出时仍在运行，可继续处理未完成的文件
+    Interrupted,
+    // 未能启动（如工具或版本不存在），附带错误消息
+    Failed,
+}
+
+// 提交到队列的一批文件，参数与 start_job 相同
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct QueueBatch {
+    pub id: String,
+    pub tool_name: String,
+    pub version: Option<String>,
+    pub file_paths: Vec<String>,
+    pub options: Map<String, Value>,
+    pub run: RunOptions,
+    pub language: String,
+    pub state: BatchState,
+    // 运行中批次对应的作业 ID，可用于 cancel_job
+    pub job_id: Option<String>,
+    // 由监视文件夹提交时为监视 ID，运行生成的结果文件记录到该监视，之后的扫描跳过这些文件
+    #[serde(default)]
+    pub watch_id: Option<String>,
+    // 已运行结束的文件结果，按输入顺序排列；不含执行记录，完整输出见作业事件与日志目录
+    pub results: Vec<ProcessResult>,
+    // 未能启动时的错误消息
+    pub error: Option<String>,
+    // 提交、最近一次开始及结束时间（Unix 毫秒）
+    pub submitted_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+}
+
+impl QueueBatch {
+    // 尚未运行结束的文件：继续处理时只运行这些文件
+    pub fn pending_files(&self) -> Vec<String> {
+        self.file_paths
+            .iter()
+            .filter(|path| {
+                !self.results.iter().any(|result| {
+                    result.status == FileStatus::Completed
+                        && result.file_path.as_deref() == Some(path.as_str())
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    // 记录文件结果，替换该文件此前的结果并保持输入顺序
+    // 去掉执行记录（命令行与捕获的输出），queue.json 只保存状态、消息与输出文件
+    fn record(&mut self, mut result: ProcessResult) {
+        result.execution = None;
+        for attempt in &mut result.previous_attempts {
+            attempt.execution = None;
+        }
+        self.results.retain(|r| r.file_path != result.file_path);
+        self.results.push(result);
+        let file_paths = &self.file_paths;
+        self.results.sort_by_key(|result| {
+            file_paths
+                .iter()
+                .position(|path| result.file_path.as_deref() == Some(path.as_str()))
+        });
+    }
+}
+
+// 批次队列，首次访问时从磁盘加载
+static QUEUE: LazyLock<Mutex<Vec<QueueBatch>>> =
+    LazyLock::new(|| Mutex::new(load(&paths::queue_file())));
+
+// 有新的待运行批次时唤醒队列调度
+static WORK: Notify = Notify::const_new();
+
+// 串行写入 queue.json，保证后取的快照后写入
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+// 已安排延迟保存时为 true，延迟期间记录的文件结果合并为一次写入
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+// 文件结果的保存延迟；应用在此期间退出时丢失的结果在继续批次时重新运行
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+// 批次序号，与提交时间一起生成唯一的批次 ID
+static BATCH_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// 读取保存的队列，文件不存在或无法解析时为空
+// 上次退出时仍在运行的批次标记为已中断，等待用户继续
+fn load(path: &Path) -> Vec<QueueBatch> {
+    let mut batches: Vec<QueueBatch> = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    for batch in &mut batches {
+        if batch.state == BatchState::Running {
+            batch.state = BatchState::Interrupted;
+            batch.job_id = None;
+        }
+    }
+    batches
+}
+
+// 保存队列到磁盘，先写临时文件再重命名，避免退出时留下不完整的文件
+// 保存失败不影响运行中的批次，仅在重启后丢失最近的变更
+fn save() {
+    let _guard = SAVE_LOCK.lock().unwrap();
+    let Ok(content) = serde_json::to_string(&*QUEUE.lock().unwrap()) else {
+        return;
+    };
+    let path = paths::queue_file();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let temp = path.with_extension("json.tmp");
+    if fs::write(&temp, content).is_ok() {
+        let _ = fs::rename(&temp, &path);
+    }
+}
+
+// 修改队列并保存
+fn update<T>(f: impl FnOnce(&mut Vec<QueueBatch>) -> T) -> T {
+    let value = f(&mut QUEUE.lock().unwrap());
+    save();
+    value
+}
+
+// 提交批次到队尾，返回批次 ID
+pub(crate) fn submit(
+    tool_name: String,
+    version: Option<String>,
+    file_paths: Vec<String>,
+    options: Map<String, Value>,
+    run: RunOptions,
+    language: String,
+    watch_id: Option<String>,
+) -> String {
+    let submitted_at = now_millis();
+    let id = format!(
+        "batch-{}-{}",
+        submitted_at,
+        BATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    update(|batches| {
+        batches.push(QueueBatch {
+            id: id.clone(),
+            tool_name,
+            version,
+            file_paths,
+            options,
+            run,
+            language,
+            state: BatchState::Queued,
+            job_id: None,
+            watch_id,
+            results: Vec::new(),
+            error: None,
+            submitted_at,
+            started_at: None,
+            finished_at: None,
+        })
+    });
+    WORK.notify_one();
+    id
+}
+
+// 批次是否仍在排队或运行中
+pub(crate) fn is_active(id: &str) -> bool {
+    QUEUE.lock().unwrap().iter().any(|batch| {
+        batch.id == id && matches!(batch.state, BatchState::Queued | BatchState::Running)
+    })
+}
+
+// 获取全部批次（按队列顺序）
+pub(crate) fn list() -> Vec<QueueBatch> {
+    QUEUE.lock().unwrap().clone()
+}
+
+// 将批次移动到队列中的指定位置（超出范围时移到队尾），批次不存在时返回 false
+pub(crate) fn move_to(id: &str, position: usize) -> bool {
+    update(|batches| {
+        let Some(index) = batches.iter().position(|batch| batch.id == id) else {
+            return false;
+        };
+        let batch = batches.remove(index);
+        let position = position.min(batches.len());
+        batches.insert(position, batch);
+        true
+    })
+}
+
+// 从队列中移除批次并返回；运行中的批次需由调用方取消其作业
+pub(crate) fn remove(id: &str) -> Option<QueueBatch> {
+    update(|batches| {
+        let index = batches.iter().position(|batch| batch.id == id)?;
+        Some(batches.remove(index))
+    })
+}
+
+// 移除全部已结束的批次（已完成、已取消、已中断及未能启动）
+pub(crate) fn clear_finished() {
+    update(|batches| {
+        batches.retain(|batch| matches!(batch.state, BatchState::Queued | BatchState::Running))
+    });
+}
+
+// 将已取消、已中断或未能启动的批次重新排队，继续处理未完成的文件
+// 批次不存在或状态不可继续时返回 false
+pub(crate) fn resume(id: &str) -> bool {
+    let resumed = update(
+        |batches| match batches.iter_mut().find(|batch| batch.id == id) {
+            Some(batch)
+                if matches!(
+                    batch.state,
+                    BatchState::Cancelled | BatchState::Interrupted | BatchState::Failed
+                ) =>
+            {
+                batch.state = BatchState::Queued;
+                batch.error = None;
+                batch.finished_at = None;
+                true
+            }
+            _ => false,
+        },
+    );
+    if resumed {
+        WORK.notify_one();
+    }
+    resumed
+}
+
+// 取出队列中第一个待运行的批次并标记为运行中
+pub(crate) fn next() -> Option<QueueBatch> {
+    update(|batches| {
+        let batch = batches
+            .iter_mut()
+            .find(|batch| batch.state == BatchState::Queued)?;
+        batch.state = BatchState::Running;
+        batch.started_at = Some(now_millis());
+        Some(batch.clone())
+    })
+}
+
+// 等待新的待运行批次
+pub(crate) async fn wait_for_work() {
+    WORK.notified().await;
+}
+
+// 记录运行中批次的作业 ID
+pub(crate) fn set_job(id: &str, job_id: &str) {
+    update(|batches| {
+        if let Some(batch) = batches.iter_mut().find(|batch| batch.id == id) {
+            batch.job_id = Some(job_id.to_string());
+        }
+    });
+}
+
+// 记录批次中单个文件的结果；批次已被移除时忽略
+// 在作业事件中调用，不在此写盘：延迟 SAVE_DELAY 后在阻塞线程池中合并保存
+pub(crate) fn record_result(id: &str, result: &ProcessResult) {
+    let recorded = match QUEUE
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .find(|batch| batch.id == id)
+    {
+        Some(batch) => {
+            batch.record(result.clone());
+            true
+        }
+        None => false,
+    };
+    if recorded && !SAVE_PENDING.swap(true, Ordering::AcqRel) {
+        tokio::spawn(async {
+            tokio::time::sleep(SAVE_DELAY).await;
+            // 先清除标记再取快照，此后记录的结果会安排下一次保存
+            SAVE_PENDING.store(false, Ordering::Release);
+            let _ = tokio::task::spawn_blocking(save).await;
+        });
+    }
+}
+
+// 批次运行结束：合并最终结果，全部文件运行结束时标记为已完成，否则为已取消
+pub(crate) fn finish(id: &str, results: Vec<ProcessResult>) {
+    update(|batches| {
+        if let Some(batch) = batches.iter_mut().find(|batch| batch.id == id) {
+            for result in results {
+                batch.record(result);
+            }
+            batch.state = if batch.pending_files().is_empty() {
+                BatchState::Completed
+            } else {
+                BatchState::Cancelled
+            };
+            batch.job_id = None;
+            batch.finished_at = Some(now_millis());
+        }
+    });
+}
+
+// 批次未能启动
+pub(crate) fn fail(id: &str, error: String) {
+    update(|batches| {
+        if let Some(batch) = batches.iter_mut().find(|batch| batch.id == id) {
+            batch.state = BatchState::Failed;
+            batch.error = Some(error);
+            batch.finished_at = Some(now_millis());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::ExecutionRecord;
+    use crate::{AttemptRecord, ResultCode};
+
+    fn batch(files: &[&str]) -> QueueBatch {
+        QueueBatch {
+            id: "batch-1".to_string(),
+            tool_name: "AneuFiler".to_string(),
+            version: None,
+            file_paths: files.iter().map(|file| file.to_string()).collect(),
+            options: Map::new(),
+            run: RunOptions::default(),
+            language: "en".to_string(),
+            state: BatchState::Queued,
+            job_id: None,
+            watch_id: None,
+            results: Vec::new(),
+            error: None,
+            submitted_at: 0,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    fn result(file: &str, code: ResultCode) -> ProcessResult {
+        ProcessResult::new(file, code, "en")
+    }
+
+    fn recorded(batch: &QueueBatch) -> Vec<(&str, ResultCode)> {
+        batch
+            .results
+            .iter()
+            .map(|result| (result.file_path.as_deref().unwrap(), result.code))
+            .collect()
+    }
+
+    #[test]
+    fn pending_files_skip_only_completed_files() {
+        let mut batch = batch(&["a.txt", "b.txt", "c.txt", "d.txt"]);
+        assert_eq!(batch.pending_files(), batch.file_paths);
+
+        batch.record(result("a.txt", ResultCode::Success));
+        batch.record(result("b.txt", ResultCode::NonzeroExit));
+        batch.record(result("c.txt", ResultCode::Cancelled));
+        batch.record(result("d.txt", ResultCode::NotStarted));
+        // 失败的文件已运行结束，继续批次时不再运行；已取消与未开始的文件重新运行
+        assert_eq!(batch.pending_files(), vec!["c.txt", "d.txt"]);
+    }
+
+    #[test]
+    fn record_replaces_earlier_results_in_input_order() {
+        let mut batch = batch(&["a.txt", "b.txt", "c.txt"]);
+        batch.record(result("c.txt", ResultCode::Cancelled));
+        batch.record(result("a.txt", ResultCode::Success));
+        batch.record(result("c.txt", ResultCode::Success));
+        assert_eq!(
+            recorded(&batch),
+            vec![
+                ("a.txt", ResultCode::Success),
+                ("c.txt", ResultCode::Success)
+            ]
+        );
+    }
+
+    #[test]
+    fn record_drops_execution_records() {
+        let execution = ExecutionRecord::new(&tokio::process::Command::new("AneuFiler"));
+        let mut failed = result("a.txt", ResultCode::NonzeroExit);
+        failed.execution = Some(execution.clone());
+        let mut retried = result("a.txt", ResultCode::Success);
+        retried.execution = Some(execution);
+        retried.previous_attempts = vec![AttemptRecord::new(1, failed)];
+
+        let mut batch = batch(&["a.txt"]);
+        batch.record(retried);
+        let saved = &batch.results[0];
+        assert!(saved.execution.is_none());
+        assert_eq!(saved.previous_attempts.len(), 1);
+        assert!(saved.previous_attempts[0].execution.is_none());
+    }
+
+    #[test]
+    fn load_marks_running_batches_interrupted() {
+        let dir = std::env::temp_dir().join(format!("cmtools-queue-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("queue.json");
+        assert!(load(&path).is_empty());
+
+        let mut running = batch(&["a.txt"]);
+        running.state = BatchState::Running;
+        running.job_id = Some("job-1".to_string());
+        let mut queued = batch(&["b.txt"]);
+        queued.id = "batch-2".to_string();
+        let mut completed = batch(&["c.txt"]);
+        completed.id = "batch-3".to_string();
+        completed.state = BatchState::Completed;
+        fs::write(
+            &path,
+            serde_json::to_string(&[running, queued, completed]).unwrap(),
+        )
+        .unwrap();
+
+        let states: Vec<(String, BatchState, Option<String>)> = load(&path)
+            .into_iter()
+            .map(|batch| (batch.id, batch.state, batch.job_id))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("batch-1".to_string(), BatchState::Interrupted, None),
+                ("batch-2".to_string(), BatchState::Queued, None),
+                ("batch-3".to_string(), BatchState::Completed, None),
+            ]
+        );
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load(&path).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
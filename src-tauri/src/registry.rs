@@ -0,0 +1,347 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::ProcessError;
+use crate::manifest::{self, ToolDef};
+use crate::settings;
+
+// 内置（嵌入）构建的版本标识
+pub(crate) const BUILTIN_VERSION: &str = "builtin";
+
+// 外部工具目录中每个版本目录下的清单文件名
+const EXTERNAL_MANIFEST_FILE: &str = "manifest.json";
+
+// 工具构建的来源
+#[derive(Debug)]
+pub(crate) enum BuildSource {
+    // 编译时嵌入的二进制数据及 build.rs 计算的 SHA-256
+    Embedded {
+        data: &'static [u8],
+        sha256: &'static str,
+    },
+    // 外部工具目录中的可执行文件
+    External {
+        path: PathBuf,
+        sha256: String,
+    },
+}
+
+// 某个工具的一个可用构建
+#[derive(Debug)]
+pub(crate) struct ToolBuild {
+    pub def: ToolDef,
+    pub version: String,
+    pub source: BuildSource,
+}
+
+// 外部工具清单：在 tools.json 单个工具定义的基础上增加校验和与覆盖标记
+#[derive(Debug, Deserialize)]
+struct ExternalManifest {
+    #[serde(flatten)]
+    tool: ToolDef,
+    // 可执行文件的 SHA-256（十六进制）
+    sha256: String,
+    // 为 true 时替代内置版本成为默认构建，否则与内置版本并存
+    #[serde(default, rename = "override")]
+    override_builtin: bool,
+}
+
+// 同名工具的全部构建
+#[derive(Debug)]
+struct ToolEntry {
+    name: String,
+    builds: Vec<Arc<ToolBuild>>,
+    // 默认构建在 builds 中的下标
+    default: usize,
+}
+
+// 工具注册表：内置工具与外部工具目录中发现的工具
+#[derive(Debug)]
+pub(crate) struct ToolRegistry {
+    entries: Vec<ToolEntry>,
+    // 加载外部工具时遇到的问题（清单无效、校验和不匹配等）
+    pub problems: Vec<String>,
+}
+
+static REGISTRY: LazyLock<RwLock<Arc<ToolRegistry>>> = LazyLock::new(|| {
+    let tools_dir = settings::current().effective_tools_dir();
+    RwLock::new(Arc::new(ToolRegistry::load(&tools_dir)))
+});
+
+// 获取当前注册表
+pub(crate) fn current() -> Arc<ToolRegistry> {
+    REGISTRY.read().unwrap().clone()
+}
+
+// 按当前设置重新扫描外部工具目录
+pub(crate) fn reload() -> Arc<ToolRegistry> {
+    let tools_dir = settings::current().effective_tools_dir();
+    let registry = Arc::new(ToolRegistry::load(&tools_dir));
+    *REGISTRY.write().unwrap() = registry.clone();
+    registry
+}
+
+impl ToolRegistry {
+    // 加载内置工具并扫描外部工具目录
+    fn load(tools_dir: &Path) -> Self {
+        let mut entries: Vec<ToolEntry> = manifest::tools()
+            .iter()
+            .map(|def| ToolEntry {
+                name: def.name.clone(),
+                builds: def
+                    .embedded_binary()
+                    .map(|(data, sha256)| {
+                        Arc::new(ToolBuild {
+                            def: def.clone(),
+                            version: BUILTIN_VERSION.to_string(),
+                            source: BuildSource::Embedded { data, sha256 },
+                        })
+                    })
+                    .into_iter()
+                    .collect(),
+                default: 0,
+            })
+            .collect();
+
+        let mut problems = Vec::new();
+        let mut overrides = Vec::new();
+        for (build, override_builtin) in scan_external_tools(tools_dir, &mut problems) {
+            let name = build.def.name.clone();
+            let index = match entries.iter().position(|entry| entry.name == name) {
+                Some(index) => index,
+                None => {
+                    entries.push(ToolEntry {
+                        name,
+                        builds: Vec::new(),
+                        default: 0,
+                    });
+                    entries.len() - 1
+                }
+            };
+            let entry = &mut entries[index];
+            if entry
+                .builds
+                .iter()
+                .any(|existing| existing.version == build.version)
+            {
+                problems.push(format!(
+                    "{} {}: duplicate version",
+                    entry.name, build.version
+                ));
+                continue;
+            }
+            entry.builds.push(Arc::new(build));
+            if override_builtin {
+                overrides.push((index, entry.builds.len() - 1));
+            }
+        }
+
+        // 默认构建：覆盖内置的外部构建中版本最高者 > 内置构建 > 版本最高的外部构建
+        for (index, entry) in entries.iter_mut().enumerate() {
+            let overriding = overrides
+                .iter()
+                .filter(|(entry_index, _)| *entry_index == index)
+                .map(|(_, build_index)| *build_index)
+                .max_by(|a, b| {
+                    compare_versions(&entry.builds[*a].version, &entry.builds[*b].version)
+                });
+            let builtin = entry
+                .builds
+                .iter()
+                .position(|build| matches!(build.source, BuildSource::Embedded { .. }));
+            let newest = (0..entry.builds.len()).max_by(|a, b| {
+                compare_versions(&entry.builds[*a].version, &entry.builds[*b].version)
+            });
+            entry.default = overriding.or(builtin).or(newest).unwrap_or(0);
+        }
+
+        ToolRegistry { entries, problems }
+    }
+
+    // 全部可用工具的默认构建（内置工具在前，外部新增工具在后）
+    pub fn tools(&self) -> impl Iterator<Item = &Arc<ToolBuild>> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.builds.get(entry.default))
+    }
+
+    // 按名称查找工具构建，未指定版本时返回默认构建
+    pub fn find(&self, name: &str, version: Option<&str>) -> Result<Arc<ToolBuild>, ProcessError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ProcessError::UnknownTool {
+                tool: name.to_string(),
+            })?;
+
+        let build =
+            match version {
+                Some(version) => entry
+                    .builds
+                    .iter()
+                    .find(|build| build.version == version)
+                    .ok_or_else(|| ProcessError::UnknownVersion {
+                        tool: name.to_string(),
+                        version: version.to_string(),
+                    })?,
+                None => entry.builds.get(entry.default).ok_or_else(|| {
+                    ProcessError::ToolUnavailable {
+                        tool: name.to_string(),
+                    }
+                })?,
+            };
+        Ok(build.clone())
+    }
+
+    // 工具的全部可用版本（按版本号升序）
+    pub fn versions(&self, name: &str) -> Vec<String> {
+        let mut versions: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .flat_map(|entry| entry.builds.iter().map(|build| build.version.clone()))
+            .collect();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        versions
+    }
+}
+
+impl ToolBuild {
+    // 构建可执行文件的 SHA-256，用于记录实际运行的构建
+    pub fn sha256(&self) -> &str {
+        match &self.source {
+            BuildSource::Embedded { sha256, .. } => sha256,
+            BuildSource::External { sha256, .. } => sha256,
+        }
+    }
+}
+
+// 扫描 <tools_dir>/<name>/<version>/manifest.json，返回通过校验的构建及其覆盖标记
+fn scan_external_tools(tools_dir: &Path, problems: &mut Vec<String>) -> Vec<(ToolBuild, bool)> {
+    let mut builds = Vec::new();
+
+    for tool_dir in sorted_subdirs(tools_dir) {
+        for version_dir in sorted_subdirs(&tool_dir) {
+            match load_external_build(&tool_dir, &version_dir) {
+                Ok(build) => builds.push(build),
+                Err(message) => problems.push(format!("{}: {}", version_dir.display(), message)),
+            }
+        }
+    }
+
+    builds
+}
+
+// 加载并校验单个外部工具版本目录
+fn load_external_build(tool_dir: &Path, version_dir: &Path) -> Result<(ToolBuild, bool), String> {
+    let manifest_path = version_dir.join(EXTERNAL_MANIFEST_FILE);
+    let content = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("cannot read {}: {}", EXTERNAL_MANIFEST_FILE, e))?;
+    let manifest: ExternalManifest = serde_json::from_str(&content)
+        .map_err(|e| format!("invalid {}: {}", EXTERNAL_MANIFEST_FILE, e))?;
+
+    let dir_name = tool_dir.file_name().unwrap_or_default().to_string_lossy();
+    if manifest.tool.name != dir_name {
+        return Err(format!(
+            "tool name {} does not match directory {}",
+            manifest.tool.name, dir_name
+        ));
+    }
+
+    let exe_path = version_dir.join(manifest.tool.exe_name());
+    let actual = sha256_file(&exe_path).map_err(|e| format!("cannot read executable: {}", e))?;
+    if !actual.eq_ignore_ascii_case(manifest.sha256.trim()) {
+        return Err(format!(
+            "checksum mismatch for {}: expected {}, got {}",
+            exe_path.display(),
+            manifest.sha256.trim(),
+            actual
+        ));
+    }
+
+    let version = version_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let build = ToolBuild {
+        def: manifest.tool,
+        version,
+        source: BuildSource::External {
+            path: exe_path,
+            sha256: actual,
+        },
+    };
+    Ok((build, manifest.override_builtin))
+}
+
+// 按名称排序的子目录列表，目录不存在时返回空
+fn sorted_subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+// 计算文件的 SHA-256（小写十六进制）
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// 比较版本号：按 `.`/`-` 分段，数字段按数值比较，其余按字符串比较
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split =
+        |version: &str| -> Vec<String> { version.split(['.', '-']).map(str::to_string).collect() };
+    let (a, b) = (split(a), split(b));
+    for (left, right) in a.iter().zip(b.iter()) {
+        let ordering = match (left.parse::<u64>(), right.parse::<u64>()) {
+            (Ok(left), Ok(right)) => left.cmp(&right),
+            _ => left.cmp(right),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_versions_orders_numerically() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0", "10.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.3", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0-rc1", "1.2.0-rc2"), Ordering::Less);
+        assert_eq!(
+            compare_versions("1.2.0-beta", "1.2.0-alpha"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn compare_versions_sorts_a_list() {
+        let mut versions = vec!["1.10", "1.2", "1.9.1", "0.9", "1.2-hotfix"];
+        versions.sort_by(|a, b| compare_versions(a, b));
+        assert_eq!(versions, vec!["0.9", "1.2", "1.2-hotfix", "1.9.1", "1.10"]);
+    }
+}
@@ -0,0 +1,582 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+use crate::ProcessError;
+use crate::jobs::CancelToken;
+use crate::paths;
+
+// 进程退出后等待管道读完的时间上限
+// 工具派生的进程脱离进程组并继续持有管道时，不至于一直等待
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 每路输出在内存中（及结果中）保留的字节数上限，超出时完整输出写入日志文件
+const OUTPUT_CAPTURE_LIMIT: usize = 64 * 1024;
+
+// 输出日志的保留时间
+const LOG_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// 重试等待时间的上限
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+// 失败文件的自动重试策略，如网络共享短暂不可用或文件被 Excel 短暂占用
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RetryPolicy {
+    // 最大尝试次数（含首次运行），1 表示不重试
+    pub max_attempts: u32,
+    // 首次重试前的等待时间（毫秒），之后每次翻倍，最长 60 秒
+    pub backoff_ms: u64,
+    // 可重试的错误类型（ProcessError 的 kind），须为 RETRYABLE_KINDS 之一
+    pub retry_on: Vec<String>,
+}
+
+// 处理单个文件时可能出现、可配置重试的错误类型
+// 文件被占用时工具通常以非零退出码结束（nonzero_exit），网络共享断开时为 file_not_found 或 io
+pub(crate) const RETRYABLE_KINDS: &[&str] = &[
+    "io",
+    "file_not_found",
+    "nonzero_exit",
+    "signaled",
+    "timeout",
+    "command_failed",
+    "executable_not_found",
+    "permission_denied",
+    "output_dir_unavailable",
+    "file_processing",
+];
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            // 默认不重试：格式错误的导出文件、错误的 STD 名称等可预见的失败重试也不会成功
+            max_attempts: 1,
+            backoff_ms: 2000,
+            retry_on: ["io", "file_not_found", "nonzero_exit", "command_failed"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // 检查 retry_on 中的错误类型，拼写错误的类型不会匹配任何失败，静默地不重试
+    pub fn validate(&self) -> Result<(), ProcessError> {
+        match self
+            .retry_on
+            .iter()
+            .find(|kind| !RETRYABLE_KINDS.contains(&kind.as_str()))
+        {
+            Some(kind) => Err(ProcessError::InvalidRetryKind { kind: kind.clone() }),
+            None => Ok(()),
+        }
+    }
+
+    // 第 attempt 次尝试以 kind 类型的错误失败后是否再次尝试
+    pub fn should_retry(&self, attempt: u32, kind: &str) -> bool {
+        attempt < self.max_attempts && self.retry_on.iter().any(|retry| retry == kind)
+    }
+
+    // 第 attempt 次尝试失败后、下一次尝试前的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor)).min(MAX_RETRY_BACKOFF)
+    }
+}
+
+// 外部工具的运行结果
+pub(crate) enum RunOutcome {
+    // 进程自行退出
+    Exited(ExitStatus),
+    // 超时后进程（及其子进程）已被终止
+    TimedOut,
+    // 作业取消后进程（及其子进程）已被终止
+    Cancelled,
+}
+
+// 捕获的一路输出（stdout 或 stderr）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CapturedOutput {
+    // 输出内容（最多 OUTPUT_CAPTURE_LIMIT 字节，按 UTF-8 有损转换）
+    pub text: String,
+    // 输出超出上限被截断
+    pub truncated: bool,
+    // 截断时完整输出所在的日志文件
+    pub log_file: Option<String>,
+}
+
+// 单个文件的执行记录，无需重新运行即可排查工具的处理结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ExecutionRecord {
+    // 实际执行的程序、参数及工作目录
+    pub program: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<String>,
+    // 退出码；被信号终止或被强制结束时为空
+    pub exit_code: Option<i32>,
+    // 终止进程的信号（仅 Unix）
+    pub signal: Option<i32>,
+    // 从启动到退出（或被终止）的耗时
+    pub duration_ms: u64,
+    pub stdout: CapturedOutput,
+    pub stderr: CapturedOutput,
+}
+
+impl ExecutionRecord {
+    // 记录即将执行的命令
+    pub fn new(cmd: &Command) -> Self {
+        let cmd = cmd.as_std();
+        ExecutionRecord {
+            program: cmd.get_program().to_string_lossy().to_string(),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            working_dir: cmd.get_current_dir().map(|dir| dir.display().to_string()),
+            exit_code: None,
+            signal: None,
+            duration_ms: 0,
+            stdout: CapturedOutput::default(),
+            stderr: CapturedOutput::default(),
+        }
+    }
+
+    fn record_status(&mut self, status: ExitStatus) {
+        self.exit_code = status.code();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            self.signal = status.signal();
+        }
+    }
+}
+
+// 以独立进程组启动命令并等待退出，超时或作业取消时终止整个进程组
+// 退出状态、耗时及输出写入 record；超出上限的输出保存到 <log_prefix>.stdout.log / .stderr.log
+pub(crate) async fn run(
+    mut cmd: Command,
+    limit: Duration,
+    mut cancel: CancelToken,
+    log_prefix: &Path,
+    record: &mut ExecutionRecord,
+) -> io::Result<RunOutcome> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    // 新建进程组，超时时可一并终止工具派生的子进程
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    // 在 Windows 上隐藏命令行窗口
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let started = Instant::now();
+    let mut child = cmd.spawn()?;
+    let stdout = capture(child.stdout.take(), log_path(log_prefix, "stdout"));
+    let stderr = capture(child.stderr.take(), log_path(log_prefix, "stderr"));
+
+    let outcome = tokio::select! {
+        status = timeout(limit, child.wait()) => match status {
+            Ok(status) => {
+                let status = status?;
+                record.record_status(status);
+                RunOutcome::Exited(status)
+            }
+            Err(_) => RunOutcome::TimedOut,
+        },
+        _ = cancel.cancelled() => RunOutcome::Cancelled,
+    };
+    record.duration_ms = started.elapsed().as_millis() as u64;
+
+    if !matches!(outcome, RunOutcome::Exited(_)) {
+        kill_process_tree(&mut child).await;
+    }
+    record.stdout = drain(stdout).await;
+    record.stderr = drain(stderr).await;
+    Ok(outcome)
+}
+
+// <log_prefix>.<stream>.log：追加到文件名后，不替换 <序号>.retry<N> 中的 .retry<N>
+fn log_path(log_prefix: &Path, stream: &str) -> PathBuf {
+    let mut name = log_prefix.as_os_str().to_owned();
+    name.push(format!(".{}.log", stream));
+    PathBuf::from(name)
+}
+
+// 在后台读取管道的全部输出，避免管道写满导致子进程阻塞
+// 内存中保留前 OUTPUT_CAPTURE_LIMIT 字节，超出时将完整输出写入日志文件
+fn capture<R>(pipe: Option<R>, log_path: PathBuf) -> JoinHandle<CapturedOutput>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut head = Vec::new();
+        let mut truncated = false;
+        let mut log: Option<tokio::fs::File> = None;
+
+        if let Some(mut pipe) = pipe {
+            let mut buffer = [0u8; 8192];
+            loop {
+                let read = match pipe.read(&mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => read,
+                };
+                let chunk = &buffer[..read];
+
+                if !truncated && head.len() + read > OUTPUT_CAPTURE_LIMIT {
+                    truncated = true;
+                    log = open_log(&log_path, &head).await.ok();
+                }
+                if let Some(file) = log.as_mut()
+                    && file.write_all(chunk).await.is_err()
+                {
+                    log = None;
+                }
+
+                let room = OUTPUT_CAPTURE_LIMIT - head.len();
+                head.extend_from_slice(&chunk[..read.min(room)]);
+            }
+        }
+
+        if let Some(file) = log.as_mut() {
+            let _ = file.flush().await;
+        }
+        CapturedOutput {
+            text: String::from_utf8_lossy(&head).to_string(),
+            truncated,
+            log_file: log.map(|_| log_path.display().to_string()),
+        }
+    })
+}
+
+// 创建日志文件并写入已读取的输出
+async fn open_log(path: &Path, head: &[u8]) -> io::Result<tokio::fs::File> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = tokio::fs::File::create(path).await?;
+    file.write_all(head).await?;
+    Ok(file)
+}
+
+// 等待后台读取完成，超时则放弃剩余输出
+async fn drain(mut reader: JoinHandle<CapturedOutput>) -> CapturedOutput {
+    match timeout(PIPE_DRAIN_TIMEOUT, &mut reader).await {
+        Ok(Ok(output)) => output,
+        _ => {
+            reader.abort();
+            CapturedOutput::default()
+        }
+    }
+}
+
+// 终止子进程及其派生的所有进程，并回收子进程
+pub(crate) async fn kill_process_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(unix)]
+        {
+            // 进程组号与子进程 pid 相同，负数表示向整个进程组发送信号
+            // SAFETY: 仅向本进程创建的进程组发送信号
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let _ = Command::new("taskkill")
+                .args(["/PID", &pid.to_string(), "/T", "/F"])
+                .creation_flags(0x08000000) // CREATE_NO_WINDOW
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await;
+        }
+    }
+
+    let _ = child.kill().await;
+}
+
+// 删除超过保留时间的作业输出日志
+pub(crate) fn remove_expired_logs() -> io::Result<()> {
+    remove_logs_older_than(&paths::run_log_dir(), LOG_RETENTION)
+}
+
+// 删除 dir 中修改时间早于 retention 之前的各作业日志目录
+fn remove_logs_older_than(dir: &Path, retention: Duration) -> io::Result<()> {
+    let now = SystemTime::now();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| {
+                now.duration_since(modified)
+                    .is_ok_and(|age| age > retention)
+            });
+        if expired {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_attempts: u32, retry_on: &[&str]) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            backoff_ms: 2000,
+            retry_on: retry_on.iter().map(|kind| kind.to_string()).collect(),
+        }
+    }
+
+    // 系统临时目录中的空测试目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cmtools-runner-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // 以 sh 运行脚本，返回运行结果及执行记录
+    #[cfg(unix)]
+    async fn run_sh(
+        script: &str,
+        limit: Duration,
+        log_prefix: &Path,
+    ) -> (RunOutcome, ExecutionRecord) {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", script]);
+        let mut record = ExecutionRecord::new(&cmd);
+        let (_cancel_tx, cancel) = CancelToken::channel();
+        let outcome = run(cmd, limit, cancel, log_prefix, &mut record)
+            .await
+            .unwrap();
+        (outcome, record)
+    }
+
+    #[test]
+    fn default_policy_does_not_retry() {
+        let retry = RetryPolicy::default();
+        assert!(retry.validate().is_ok());
+        assert!(!retry.should_retry(1, "io"));
+    }
+
+    #[test]
+    fn should_retry_listed_kinds_until_max_attempts() {
+        let retry = policy(3, &["io", "nonzero_exit"]);
+        assert!(retry.should_retry(1, "io"));
+        assert!(retry.should_retry(2, "nonzero_exit"));
+        assert!(!retry.should_retry(3, "io"));
+        assert!(!retry.should_retry(1, "timeout"));
+        assert!(!policy(1, &["io"]).should_retry(1, "io"));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let retry = policy(10, &[]);
+        assert_eq!(retry.backoff(1), Duration::from_secs(2));
+        assert_eq!(retry.backoff(2), Duration::from_secs(4));
+        assert_eq!(retry.backoff(3), Duration::from_secs(8));
+        assert_eq!(retry.backoff(6), MAX_RETRY_BACKOFF);
+        assert_eq!(retry.backoff(200), MAX_RETRY_BACKOFF);
+
+        let retry = RetryPolicy {
+            backoff_ms: u64::MAX,
+            ..retry
+        };
+        assert_eq!(retry.backoff(2), MAX_RETRY_BACKOFF);
+    }
+
+    #[test]
+    fn validate_rejects_unknown_kinds() {
+        assert!(policy(3, RETRYABLE_KINDS).validate().is_ok());
+        assert!(policy(3, &[]).validate().is_ok());
+        match policy(3, &["io", "nonzero-exit"]).validate() {
+            Err(ProcessError::InvalidRetryKind { kind }) => assert_eq!(kind, "nonzero-exit"),
+            other => panic!("expected InvalidRetryKind, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn log_path_keeps_the_attempt_suffix() {
+        let dir = Path::new("/logs/job-1");
+        assert_eq!(log_path(&dir.join("3"), "stdout"), dir.join("3.stdout.log"));
+        assert_eq!(
+            log_path(&dir.join("3.retry1"), "stderr"),
+            dir.join("3.retry1.stderr.log")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn each_attempt_keeps_its_own_log() {
+        let dir = temp_dir("attempt-logs");
+        let script = |n: u32| format!("yes {} | head -c 100000", n);
+        let limit = Duration::from_secs(10);
+
+        let (_, first) = run_sh(&script(1), limit, &dir.join("3")).await;
+        let (_, retry) = run_sh(&script(2), limit, &dir.join("3.retry1")).await;
+
+        let first_log = first.stdout.log_file.unwrap();
+        let retry_log = retry.stdout.log_file.unwrap();
+        assert_ne!(first_log, retry_log);
+        assert!(fs::read_to_string(first_log).unwrap().starts_with("1\n1\n"));
+        assert!(fs::read_to_string(retry_log).unwrap().starts_with("2\n2\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // 进程已退出（不存在或已成为僵尸进程）
+    #[cfg(target_os = "linux")]
+    fn process_gone(pid: &str) -> bool {
+        match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Err(_) => true,
+            Ok(stat) => stat
+                .rsplit_once(')')
+                .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z')),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn timeout_kills_the_whole_process_group() {
+        let dir = temp_dir("timeout-group");
+        let pid_file = dir.join("child.pid");
+        // sh 派生的 sleep 与 sh 同属一个进程组，超时后应一并被终止
+        let script = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+
+        let started = Instant::now();
+        let (outcome, record) = run_sh(&script, Duration::from_millis(500), &dir.join("0")).await;
+        assert!(matches!(outcome, RunOutcome::TimedOut));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(record.exit_code, None);
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !process_gone(pid) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(process_gone(pid), "grandchild {} survived the timeout", pid);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timed_out_run_records_elapsed_time_and_stderr() {
+        let dir = temp_dir("timeout-stderr");
+        let script = "echo 'reading plate layout' >&2; sleep 30";
+        let (outcome, record) = run_sh(script, Duration::from_millis(300), &dir.join("0")).await;
+        assert!(matches!(outcome, RunOutcome::TimedOut));
+        assert!(
+            (300..10_000).contains(&record.duration_ms),
+            "{}",
+            record.duration_ms
+        );
+        assert_eq!(record.stderr.text, "reading plate layout\n");
+
+        match ProcessError::timed_out(1, &record) {
+            ProcessError::Timeout {
+                seconds,
+                elapsed_ms,
+                stderr,
+            } => {
+                assert_eq!(seconds, 1);
+                assert_eq!(elapsed_ms, record.duration_ms);
+                assert_eq!(stderr, "reading plate layout");
+            }
+            other => panic!("expected Timeout, got {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exit_code_and_signal_are_recorded() {
+        let dir = temp_dir("exit-status");
+        let limit = Duration::from_secs(10);
+
+        let (outcome, record) = run_sh("echo 'bad STD' >&2; exit 3", limit, &dir.join("0")).await;
+        let RunOutcome::Exited(status) = outcome else {
+            panic!("expected the tool to exit");
+        };
+        assert_eq!((record.exit_code, record.signal), (Some(3), None));
+        match ProcessError::exited(status, &record) {
+            ProcessError::NonzeroExit {
+                exit_code, stderr, ..
+            } => assert_eq!((exit_code, stderr.as_str()), (3, "bad STD")),
+            other => panic!("expected NonzeroExit, got {:?}", other),
+        }
+
+        let (outcome, record) = run_sh("kill -TERM $$", limit, &dir.join("1")).await;
+        let RunOutcome::Exited(status) = outcome else {
+            panic!("expected the tool to exit");
+        };
+        assert_eq!(
+            (record.exit_code, record.signal),
+            (None, Some(libc::SIGTERM))
+        );
+        assert!(matches!(
+            ProcessError::exited(status, &record),
+            ProcessError::Signaled { signal, .. } if signal == libc::SIGTERM
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn long_output_is_truncated_and_logged_in_full() {
+        let dir = temp_dir("truncate");
+        let script = "head -c 100000 /dev/zero | tr '\\0' x; echo short >&2";
+        let (_, record) = run_sh(script, Duration::from_secs(10), &dir.join("0")).await;
+
+        assert!(record.stdout.truncated);
+        assert_eq!(record.stdout.text.len(), OUTPUT_CAPTURE_LIMIT);
+        let log_file = record.stdout.log_file.unwrap();
+        assert_eq!(Path::new(&log_file), dir.join("0.stdout.log"));
+        let logged = fs::read_to_string(log_file).unwrap();
+        assert_eq!(logged.len(), 100_000);
+        assert!(logged.bytes().all(|byte| byte == b'x'));
+
+        assert!(!record.stderr.truncated);
+        assert_eq!(record.stderr.text, "short\n");
+        assert_eq!(record.stderr.log_file, None);
+        assert!(!dir.join("0.stderr.log").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expired_log_directories_are_removed() {
+        let dir = temp_dir("expired-logs");
+        let old = dir.join("job-old");
+        let recent = dir.join("job-recent");
+        for job in [&old, &recent] {
+            fs::create_dir_all(job).unwrap();
+            fs::write(job.join("0.stdout.log"), "output").unwrap();
+        }
+        let month_ago = SystemTime::now() - Duration::from_secs(31 * 24 * 60 * 60);
+        fs::File::open(&old)
+            .unwrap()
+            .set_modified(month_ago)
+            .unwrap();
+
+        remove_logs_older_than(&dir, LOG_RETENTION).unwrap();
+        assert!(!old.exists());
+        assert!(recent.join("0.stdout.log").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use crate::ProcessError;
+use crate::paths;
+use crate::runner::RetryPolicy;
+
+// 应用设置，持久化在 <app data>/settings.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AppSettings {
+    // 外部工具目录，未设置时使用 <app data>/tools
+    pub tools_dir: Option<PathBuf>,
+    // 全局并发上限与超时，并发上限同时限制所有作业同时运行的工具进程总数
+    pub limits: RunLimits,
+    // 按工具名称覆盖并发上限与超时，优先于工具定义中的默认值
+    pub tool_limits: HashMap<String, RunLimits>,
+    // 失败文件的自动重试策略，作业可单独指定
+    pub retry: RetryPolicy,
+    // 本机 HTTP 接口服务，供 LIMS 等系统提交作业
+    pub api: ApiSettings,
+}
+
+// 默认的接口服务端口
+const DEFAULT_API_PORT: u16 = 17321;
+
+// HTTP 接口服务设置，服务仅监听本机回环地址
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ApiSettings {
+    // 启动图形界面时是否同时启动接口服务（修改后重启生效）；命令行 serve 子命令不受此限制
+    pub enabled: bool,
+    pub port: u16,
+    // 访问令牌，请求需携带 Authorization: Bearer <令牌>；未设置时首次启动服务时生成并保存
+    pub token: Option<String>,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        ApiSettings {
+            enabled: false,
+            port: DEFAULT_API_PORT,
+            token: None,
+        }
+    }
+}
+
+// 并发上限与超时设置，可出现在作业参数、应用设置及工具定义中
+// 未设置或为 0 的字段沿用下一级：作业 > 按工具设置 > 工具定义 > 全局设置 > 内置默认值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct RunLimits {
+    // 同时运行的工具进程数上限
+    pub max_parallel: Option<usize>,
+    // 单个文件的处理超时时间（秒）
+    pub timeout_secs: Option<u64>,
+}
+
+impl RunLimits {
+    // 未设置的字段取 fallback 中的值
+    pub fn or(self, fallback: RunLimits) -> RunLimits {
+        RunLimits {
+            max_parallel: self
+                .max_parallel
+                .filter(|n| *n > 0)
+                .or(fallback.max_parallel),
+            timeout_secs: self
+                .timeout_secs
+                .filter(|n| *n > 0)
+                .or(fallback.timeout_secs),
+        }
+    }
+}
+
+impl AppSettings {
+    // 实际生效的外部工具目录
+    pub fn effective_tools_dir(&self) -> PathBuf {
+        self.tools_dir
+            .clone()
+            .unwrap_or_else(paths::default_tools_dir)
+    }
+}
+
+// 当前设置，首次访问时从磁盘加载，读取失败时使用默认值
+// 重试策略无效时（如 retry_on 含未知的错误类型）输出警告并改用默认重试策略
+static SETTINGS: LazyLock<RwLock<AppSettings>> = LazyLock::new(|| {
+    let mut settings: AppSettings = fs::read_to_string(paths::settings_file())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    if let Err(e) = settings.retry.validate() {
+        eprintln!("Ignoring retry policy in settings: {}", e);
+        settings.retry = RetryPolicy::default();
+    }
+    RwLock::new(settings)
+});
+
+// 获取当前设置的副本
+pub(crate) fn current() -> AppSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+// 保存设置到磁盘并替换当前设置
+pub(crate) fn save(settings: AppSettings) -> Result<(), ProcessError> {
+    settings.retry.validate()?;
+    let path = paths::settings_file();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(&settings).map_err(io::Error::other)?;
+    write_private(&path, &content)?;
+
+    *SETTINGS.write().unwrap() = settings;
+    Ok(())
+}
+
+// 原子写入设置文件：先写入同目录下的临时文件再重命名
+// 设置中保存接口令牌，Unix 上文件仅当前用户可读写，共用工作站的其他用户无法读取令牌提交作业
+fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    let temp = path.with_extension("json.tmp");
+    // 上次中断遗留的临时文件可能权限过宽，重新创建
+    let _ = fs::remove_file(&temp);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let result = options
+        .open(&temp)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_private_replaces_the_file_atomically() {
+        let dir = std::env::temp_dir().join(format!("cmtools-settings-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        fs::write(&path, "{}").unwrap();
+
+        write_private(&path, r#"{"api":{"token":"secret"}}"#).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"{"api":{"token":"secret"}}"#
+        );
+        assert!(!path.with_extension("json.tmp").exists());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
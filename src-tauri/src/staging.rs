@@ -0,0 +1,68 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::paths;
+
+// 超过该时间仍残留的暂存目录视为异常退出遗留，清理时删除
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 单个文件的暂存目录，离开作用域时删除
+pub(crate) struct StagingDir {
+    path: PathBuf,
+}
+
+impl StagingDir {
+    // 创建空的 <app data>/staging/<job_id>/<index>/
+    pub fn create(job_id: &str, index: usize) -> io::Result<Self> {
+        let path = paths::staging_dir().join(job_id).join(index.to_string());
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::create_dir_all(&path)?;
+        Ok(StagingDir { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 将输入文件复制到暂存目录，返回副本路径
+    // 只复制内容不复制权限，只读的输入文件的副本也可正常清理
+    pub fn stage_input(&self, input: &Path) -> io::Result<PathBuf> {
+        let staged = self.path.join(input.file_name().unwrap_or_default());
+        let mut source = fs::File::open(input)?;
+        let mut target = fs::File::create(&staged)?;
+        io::copy(&mut source, &mut target)?;
+        Ok(staged)
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+        // 作业目录为空时一并删除
+        if let Some(job_dir) = self.path.parent() {
+            let _ = fs::remove_dir(job_dir);
+        }
+    }
+}
+
+// 删除异常退出遗留的暂存目录
+pub(crate) fn remove_stale() -> io::Result<()> {
+    let now = SystemTime::now();
+    for entry in fs::read_dir(paths::staging_dir())?.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| {
+                now.duration_since(modified)
+                    .is_ok_and(|age| age > STALE_AFTER)
+            });
+        if stale {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+    Ok(())
+}
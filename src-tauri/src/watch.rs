@@ -0,0 +1,565 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::inputs::{self, InputFilters};
+use crate::paths;
+use crate::registry;
+use crate::{ProcessError, RunOptions, process_error_to_localized_string};
+
+// 扫描监视文件夹的间隔
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+// 文件大小与修改时间默认需保持不变的时间（秒），仪器软件导出大文件时分多次写入
+const DEFAULT_STABLE_SECS: u64 = 10;
+
+fn default_stable_secs() -> u64 {
+    DEFAULT_STABLE_SECS
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+// 监视文件夹的设置：文件夹、处理所用的工具与参数（与 start_job 相同）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct WatchConfig {
+    pub folder: String,
+    pub tool_name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub options: Map<String, Value>,
+    #[serde(default)]
+    pub run: RunOptions,
+    #[serde(default = "default_language")]
+    pub language: String,
+    // 文件筛选规则，缺省时使用工具定义中的 input_filters
+    #[serde(default)]
+    pub filters: Option<InputFilters>,
+    // 文件大小与修改时间保持不变多少秒后视为导出完成
+    #[serde(default = "default_stable_secs")]
+    pub stable_secs: u64,
+}
+
+// 文件的大小与修改时间，任一变化即视为文件仍在写入或已被重新导出
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    modified_ms: u64,
+}
+
+impl FileStamp {
+    fn read(path: &str) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified_ms = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        Some(FileStamp {
+            size: metadata.len(),
+            modified_ms,
+        })
+    }
+}
+
+// 监视中的文件夹，持久化在 <app data>/watches.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WatchFolder {
+    id: String,
+    config: WatchConfig,
+    enabled: bool,
+    // 最近一次扫描的错误（如网络共享断开），扫描成功后清除
+    last_error: Option<String>,
+    // 最近一次提交到作业队列的批次 ID
+    last_batch_id: Option<String>,
+    // 已提交处理的文件及提交时的状态；文件被重新导出（状态变化）时再次处理
+    processed: HashMap<String, FileStamp>,
+    // 本监视提交的批次运行时生成的结果文件，扫描时跳过，避免结果文件被当作新的输入
+    #[serde(default)]
+    outputs: HashSet<String>,
+    // 等待稳定的新文件及其状态最近一次变化的时间，不持久化
+    #[serde(skip)]
+    pending: HashMap<String, (FileStamp, Instant)>,
+}
+
+impl WatchFolder {
+    // 按一次扫描的结果更新文件状态，返回导出完成的新文件（已记为已处理）及状态是否变化
+    // busy 为 true（本监视提交的批次尚未运行结束）时只跟踪文件状态，不提交新文件：
+    // 运行中的工具写入文件夹的中间文件在该文件运行结束后才记录为结果文件，期间可能已保持稳定
+    fn update_files(
+        &mut self,
+        files: Vec<(String, FileStamp)>,
+        stable_for: Duration,
+        now: Instant,
+        busy: bool,
+    ) -> (Vec<String>, bool) {
+        // 已删除的文件不再记录，之后同名文件再次出现时按新文件处理
+        let present: HashSet<&str> = files.iter().map(|(file, _)| file.as_str()).collect();
+        let before = (self.processed.len(), self.pending.len(), self.outputs.len());
+        self.processed
+            .retain(|path, _| present.contains(path.as_str()));
+        self.pending
+            .retain(|path, _| present.contains(path.as_str()));
+        self.outputs.retain(|path| present.contains(path.as_str()));
+
+        let mut stable = Vec::new();
+        for (file, stamp) in files {
+            if self.outputs.contains(&file) {
+                self.pending.remove(&file);
+                continue;
+            }
+            if self.processed.get(&file) == Some(&stamp) {
+                continue;
+            }
+            match self.pending.get(&file) {
+                Some((seen, since)) if *seen == stamp => {
+                    if !busy && now.duration_since(*since) >= stable_for {
+                        stable.push((file, stamp));
+                    }
+                }
+                _ => {
+                    self.pending.insert(file, (stamp, now));
+                }
+            }
+        }
+        for (file, stamp) in &stable {
+            self.pending.remove(file);
+            self.processed.insert(file.clone(), *stamp);
+        }
+
+        let changed = !stable.is_empty()
+            || before != (self.processed.len(), self.pending.len(), self.outputs.len());
+        (stable.into_iter().map(|(file, _)| file).collect(), changed)
+    }
+
+    // 记录结果文件，只记录位于监视文件夹内的文件；返回是否有新增
+    fn add_outputs(&mut self, files: &[String]) -> bool {
+        let folder = Path::new(&self.config.folder);
+        let before = self.outputs.len();
+        self.outputs.extend(
+            files
+                .iter()
+                .filter(|file| Path::new(file).starts_with(folder))
+                .cloned(),
+        );
+        self.outputs.len() != before
+    }
+}
+
+// 返回给界面的监视文件夹状态
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct WatchInfo {
+    pub id: String,
+    pub config: WatchConfig,
+    pub enabled: bool,
+    pub last_error: Option<String>,
+    pub last_batch_id: Option<String>,
+    // 已提交处理的文件数
+    pub processed: usize,
+    // 等待稳定的文件数
+    pub pending: usize,
+}
+
+// 一次扫描中导出完成、待提交处理的文件
+pub(crate) struct ReadyFiles {
+    pub watch_id: String,
+    pub config: WatchConfig,
+    pub files: Vec<String>,
+}
+
+static WATCHES: LazyLock<Mutex<Vec<WatchFolder>>> = LazyLock::new(|| {
+    let watches = fs::read_to_string(paths::watches_file())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    Mutex::new(watches)
+});
+
+// 记录了新的结果文件、尚未保存时为 true，由下一次扫描保存
+static OUTPUTS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+// 监视序号，与创建时间一起生成唯一的监视 ID
+static WATCH_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 保存到磁盘，先写临时文件再重命名
+fn save(watches: &[WatchFolder]) {
+    let path = paths::watches_file();
+    let Ok(content) = serde_json::to_string(watches) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let temp = path.with_extension("json.tmp");
+    if fs::write(&temp, content).is_ok() {
+        let _ = fs::rename(&temp, &path);
+    }
+}
+
+// 修改监视列表并保存
+fn update<T>(f: impl FnOnce(&mut Vec<WatchFolder>) -> T) -> T {
+    let mut watches = WATCHES.lock().unwrap();
+    let value = f(&mut watches);
+    save(&watches);
+    value
+}
+
+// 实际生效的筛选规则：监视设置中的规则，缺省时为工具定义中的默认规则
+fn effective_filters(config: &WatchConfig) -> Result<InputFilters, ProcessError> {
+    match &config.filters {
+        Some(filters) => Ok(filters.clone()),
+        None => Ok(registry::current()
+            .find(&config.tool_name, config.version.as_deref())?
+            .def
+            .input_filters
+            .clone()),
+    }
+}
+
+// 扫描文件夹，返回符合筛选规则的文件及其状态
+// 输出目录位于监视文件夹内时跳过其中的文件，避免结果文件被当作新的输入
+// 遍历整个文件夹而不受展开输入时的文件数上限限制
+fn scan(config: &WatchConfig) -> Result<Vec<(String, FileStamp)>, ProcessError> {
+    let folder = Path::new(&config.folder);
+    if !folder.is_dir() {
+        return Err(ProcessError::WatchFolderUnavailable {
+            path: config.folder.clone(),
+        });
+    }
+    let filters = effective_filters(config)?;
+    let output_dir = config.run.output.as_ref().map(|output| &output.dir);
+
+    Ok(inputs::list_dir(folder, &filters)?
+        .into_iter()
+        .filter(|file| output_dir.is_none_or(|dir| !Path::new(file).starts_with(dir)))
+        .filter_map(|file| FileStamp::read(&file).map(|stamp| (file, stamp)))
+        .collect())
+}
+
+// 添加监视文件夹，返回监视 ID
+// process_existing 为 false 时文件夹中已有的文件视为已处理，只处理之后新增或重新导出的文件
+pub(crate) fn add(config: WatchConfig, process_existing: bool) -> Result<String, ProcessError> {
+    let build = registry::current().find(&config.tool_name, config.version.as_deref())?;
+    build.def.resolve_options(&config.options)?;
+    let existing = scan(&config)?;
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let id = format!(
+        "watch-{}-{}",
+        created_at,
+        WATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let processed = if process_existing {
+        HashMap::new()
+    } else {
+        existing.into_iter().collect()
+    };
+    update(|watches| {
+        watches.push(WatchFolder {
+            id: id.clone(),
+            config,
+            enabled: true,
+            last_error: None,
+            last_batch_id: None,
+            processed,
+            outputs: HashSet::new(),
+            pending: HashMap::new(),
+        })
+    });
+    Ok(id)
+}
+
+// 移除监视文件夹，不存在时返回 false；已提交的批次不受影响
+pub(crate) fn remove(id: &str) -> bool {
+    update(|watches| {
+        let count = watches.len();
+        watches.retain(|watch| watch.id != id);
+        watches.len() != count
+    })
+}
+
+// 暂停或恢复监视，不存在时返回 false
+// 恢复后暂停期间新增的文件同样会被处理
+pub(crate) fn set_enabled(id: &str, enabled: bool) -> bool {
+    update(
+        |watches| match watches.iter_mut().find(|watch| watch.id == id) {
+            Some(watch) => {
+                watch.enabled = enabled;
+                watch.pending.clear();
+                true
+            }
+            None => false,
+        },
+    )
+}
+
+// 获取全部监视文件夹的状态
+pub(crate) fn list() -> Vec<WatchInfo> {
+    WATCHES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|watch| WatchInfo {
+            id: watch.id.clone(),
+            config: watch.config.clone(),
+            enabled: watch.enabled,
+            last_error: watch.last_error.clone(),
+            last_batch_id: watch.last_batch_id.clone(),
+            processed: watch.processed.len(),
+            pending: watch.pending.len(),
+        })
+        .collect()
+}
+
+// 扫描全部启用的监视文件夹，返回导出完成的新文件并将其记为已处理
+// batch_active 判断批次是否尚未运行结束，监视最近提交的批次运行期间暂不提交新文件
+// 文件夹扫描在锁外进行，避免网络共享响应慢时阻塞其他命令；第二个返回值表示状态是否变化
+pub(crate) fn poll(batch_active: impl Fn(&str) -> bool) -> (Vec<ReadyFiles>, bool) {
+    let configs: Vec<(String, WatchConfig)> = WATCHES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|watch| watch.enabled)
+        .map(|watch| (watch.id.clone(), watch.config.clone()))
+        .collect();
+    let scans: Vec<_> = configs
+        .into_iter()
+        .map(|(id, config)| {
+            let scan = scan(&config);
+            (id, config, scan)
+        })
+        .collect();
+
+    let now = Instant::now();
+    let mut ready = Vec::new();
+    let mut changed = false;
+    let mut watches = WATCHES.lock().unwrap();
+
+    for (id, config, scan) in scans {
+        // 扫描期间被移除或暂停的文件夹不再处理
+        let Some(watch) = watches
+            .iter_mut()
+            .find(|watch| watch.id == id && watch.enabled)
+        else {
+            continue;
+        };
+        let files = match scan {
+            Ok(files) => files,
+            Err(e) => {
+                let error = process_error_to_localized_string(&e, &config.language);
+                changed |= watch.last_error.as_ref() != Some(&error);
+                watch.last_error = Some(error);
+                continue;
+            }
+        };
+        changed |= watch.last_error.take().is_some();
+
+        let busy = watch.last_batch_id.as_deref().is_some_and(&batch_active);
+        let stable_for = Duration::from_secs(config.stable_secs);
+        let (stable, updated) = watch.update_files(files, stable_for, now, busy);
+        changed |= updated;
+        if !stable.is_empty() {
+            ready.push(ReadyFiles {
+                watch_id: id,
+                config,
+                files: stable,
+            });
+        }
+    }
+
+    if changed || OUTPUTS_CHANGED.swap(false, Ordering::AcqRel) {
+        save(&watches);
+    }
+    (ready, changed)
+}
+
+// 记录监视文件夹最近一次提交的批次
+pub(crate) fn set_last_batch(id: &str, batch_id: &str) {
+    update(|watches| {
+        if let Some(watch) = watches.iter_mut().find(|watch| watch.id == id) {
+            watch.last_batch_id = Some(batch_id.to_string());
+        }
+    });
+}
+
+// 记录监视提交的批次生成的结果文件，之后的扫描跳过这些文件；监视已被移除时忽略
+// 在作业事件中调用，不在此写盘，由下一次扫描在阻塞线程池中保存
+pub(crate) fn record_outputs(id: &str, files: &[String]) {
+    if files.is_empty() {
+        return;
+    }
+    let mut watches = WATCHES.lock().unwrap();
+    if let Some(watch) = watches.iter_mut().find(|watch| watch.id == id)
+        && watch.add_outputs(files)
+    {
+        OUTPUTS_CHANGED.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 系统临时目录中的监视文件夹，结束时删除
+    struct TempFolder(String);
+
+    impl TempFolder {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("cmtools-watch-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempFolder(dir.display().to_string())
+        }
+
+        fn path(&self, name: &str) -> String {
+            Path::new(&self.0).join(name).display().to_string()
+        }
+
+        fn write(&self, name: &str, content: &str) -> String {
+            let path = self.path(name);
+            fs::write(&path, content).unwrap();
+            path
+        }
+
+        fn watch(&self) -> WatchFolder {
+            WatchFolder {
+                id: "watch-1".to_string(),
+                config: WatchConfig {
+                    folder: self.0.clone(),
+                    tool_name: "AneuFiler".to_string(),
+                    version: None,
+                    options: Map::new(),
+                    run: RunOptions::default(),
+                    language: default_language(),
+                    filters: Some(InputFilters {
+                        include: vec!["*.txt".to_string()],
+                        exclude: Vec::new(),
+                    }),
+                    stable_secs: DEFAULT_STABLE_SECS,
+                },
+                enabled: true,
+                last_error: None,
+                last_batch_id: None,
+                processed: HashMap::new(),
+                outputs: HashSet::new(),
+                pending: HashMap::new(),
+            }
+        }
+    }
+
+    impl Drop for TempFolder {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const STABLE: Duration = Duration::from_secs(DEFAULT_STABLE_SECS);
+
+    // 在 at 时刻扫描监视文件夹，返回导出完成的文件
+    fn poll_at(watch: &mut WatchFolder, at: Instant, busy: bool) -> Vec<String> {
+        let files = scan(&watch.config).unwrap();
+        watch.update_files(files, STABLE, at, busy).0
+    }
+
+    #[test]
+    fn new_files_wait_while_the_watch_batch_runs() {
+        let folder = TempFolder::new("busy");
+        let mut watch = folder.watch();
+        let start = Instant::now();
+        let export = folder.write("S01.txt", "export");
+        assert!(poll_at(&mut watch, start, true).is_empty());
+
+        // 运行中的工具在文件夹中写入的中间文件，稳定后仍不提交
+        let side_file = folder.write("S01.tmp.txt", "intermediate");
+        assert!(poll_at(&mut watch, start, true).is_empty());
+        assert!(poll_at(&mut watch, start + STABLE * 3, true).is_empty());
+        assert_eq!(watch.pending.len(), 2);
+
+        // 文件运行结束时记录结果文件，批次结束后只提交导出文件
+        assert!(watch.add_outputs(std::slice::from_ref(&side_file)));
+        assert_eq!(poll_at(&mut watch, start + STABLE * 4, false), vec![export]);
+        assert!(watch.pending.is_empty());
+    }
+
+    #[test]
+    fn files_are_submitted_once_stable() {
+        let folder = TempFolder::new("stable");
+        let mut watch = folder.watch();
+        let start = Instant::now();
+        let export = folder.write("S01.txt", "part");
+        folder.write("notes.docx", "skipped by the filters");
+
+        assert!(poll_at(&mut watch, start, false).is_empty());
+        assert!(poll_at(&mut watch, start + STABLE / 2, false).is_empty());
+
+        // 仍在写入：大小变化后重新计时
+        folder.write("S01.txt", "part and the rest");
+        assert!(poll_at(&mut watch, start + STABLE, false).is_empty());
+        assert!(poll_at(&mut watch, start + STABLE * 3 / 2, false).is_empty());
+        assert_eq!(
+            poll_at(&mut watch, start + STABLE * 2, false),
+            vec![export.clone()]
+        );
+        assert!(poll_at(&mut watch, start + STABLE * 5, false).is_empty());
+
+        // 重新导出后再次处理
+        folder.write("S01.txt", "exported again");
+        assert!(poll_at(&mut watch, start + STABLE * 6, false).is_empty());
+        assert_eq!(
+            poll_at(&mut watch, start + STABLE * 7, false),
+            vec![export.clone()]
+        );
+
+        // 删除后不再记录
+        fs::remove_file(&export).unwrap();
+        assert!(poll_at(&mut watch, start + STABLE * 8, false).is_empty());
+        assert!(watch.processed.is_empty());
+    }
+
+    #[test]
+    fn outputs_of_the_watch_are_never_submitted() {
+        let folder = TempFolder::new("outputs");
+        let mut watch = folder.watch();
+        let start = Instant::now();
+        let export = folder.write("S01.txt", "export");
+        let result = folder.path("S01_result.txt");
+
+        assert!(poll_at(&mut watch, start, false).is_empty());
+        assert_eq!(poll_at(&mut watch, start + STABLE, false), vec![export]);
+
+        // 结果文件出现在监视文件夹中；文件夹外的文件不记录
+        fs::write(&result, "result").unwrap();
+        assert!(watch.add_outputs(&[result.clone(), "/elsewhere/S01_result.txt".to_string()]));
+        assert!(!watch.add_outputs(std::slice::from_ref(&result)));
+        assert_eq!(watch.outputs, HashSet::from([result.clone()]));
+        assert!(poll_at(&mut watch, start + STABLE * 2, false).is_empty());
+        assert!(poll_at(&mut watch, start + STABLE * 4, false).is_empty());
+
+        // 输出目录位于监视文件夹内时，其中的文件不会被扫描
+        let output_dir = Path::new(&folder.0).join("results");
+        fs::create_dir_all(&output_dir).unwrap();
+        fs::write(output_dir.join("S02_result.txt"), "result").unwrap();
+        watch.config.run.output = Some(crate::outputs::OutputOptions {
+            dir: output_dir,
+            collision: Default::default(),
+        });
+        let scanned: Vec<String> = scan(&watch.config)
+            .unwrap()
+            .into_iter()
+            .map(|(file, _)| file)
+            .collect();
+        assert!(scanned.iter().all(|file| !file.contains("results")));
+    }
+}